@@ -0,0 +1,303 @@
+use std::collections::{HashSet, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    generators::UnionFind,
+    maze::{Maze, Opening}
+};
+
+/// Texture and difficulty numbers for a maze, all computed over the
+/// enabled cells and the passages between them.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct MazeMetrics {
+    cells: u32,
+    degree_counts: Vec<u32>,
+    corridor_count: u32,
+    corridor_mean: f64,
+    corridor_longest: u32,
+    solution_length: Option<u32>,
+    tortuosity: Option<f64>,
+    components: u32,
+    loops: u32,
+    horizontal_passages: u32,
+    vertical_passages: u32,
+}
+
+#[wasm_bindgen]
+impl MazeMetrics {
+    pub fn cells(&self) -> u32 {
+        self.cells
+    }
+
+    /// Cells with exactly one way out
+    pub fn dead_ends(&self) -> u32 {
+        self.degree(1)
+    }
+
+    pub fn dead_end_ratio(&self) -> f64 {
+        ratio(self.dead_ends(), self.cells)
+    }
+
+    /// Cells with three or more ways out
+    pub fn junctions(&self) -> u32 {
+        self.degree_counts.iter().skip(3).sum()
+    }
+
+    /// Number of cells with `degree` ways out
+    pub fn degree(&self, degree: usize) -> u32 {
+        self.degree_counts.get(degree).copied().unwrap_or(0)
+    }
+
+    /// Cell counts indexed by degree
+    pub fn degree_counts(&self) -> Vec<u32> {
+        self.degree_counts.clone()
+    }
+
+    /// Corridors ("rivers") run between junctions and dead ends, with no
+    /// choice along the way
+    pub fn corridor_count(&self) -> u32 {
+        self.corridor_count
+    }
+
+    /// Mean corridor length in moves
+    pub fn corridor_mean(&self) -> f64 {
+        self.corridor_mean
+    }
+
+    pub fn corridor_longest(&self) -> u32 {
+        self.corridor_longest
+    }
+
+    /// Moves from the entrance to the exit, else from start to end, else
+    /// between opposite corners. `None` if they aren't connected.
+    pub fn solution_length(&self) -> Option<u32> {
+        self.solution_length
+    }
+
+    /// Solution length over the straight-line grid distance between its
+    /// ends. 1 is a direct route, higher winds more.
+    pub fn tortuosity(&self) -> Option<f64> {
+        self.tortuosity
+    }
+
+    /// Separate areas that can't reach each other
+    pub fn components(&self) -> u32 {
+        self.components
+    }
+
+    /// Independent cycles, 0 for a perfect maze
+    pub fn loops(&self) -> u32 {
+        self.loops
+    }
+
+    /// Share of passages that run east-west. 0.5 is unbiased, higher means
+    /// long horizontal corridors.
+    pub fn horizontal_bias(&self) -> f64 {
+        ratio(self.horizontal_passages, self.horizontal_passages + self.vertical_passages)
+    }
+}
+
+#[wasm_bindgen]
+impl Maze {
+    pub fn metrics(&self) -> MazeMetrics {
+        let (width, height) = (self.width(), self.height());
+        let index = |row: u32, col: u32| (row * width + col) as usize;
+
+        let mut cells = 0;
+        let mut degree_counts = vec![0; 5];
+        let mut passages = HashSet::new();
+        let mut horizontal_passages = 0;
+        let mut vertical_passages = 0;
+        for row in 0..height {
+            for col in 0..width {
+                if !self.is_enabled(row, col) {
+                    continue;
+                }
+                cells += 1;
+
+                let neighbors = self.enabled_passages(row, col);
+                if degree_counts.len() <= neighbors.len() {
+                    degree_counts.resize(neighbors.len() + 1, 0);
+                }
+                degree_counts[neighbors.len()] += 1;
+
+                for (nr, nc) in neighbors {
+                    let (a, b) = (index(row, col), index(nr, nc));
+                    if passages.insert((a.min(b), a.max(b))) {
+                        if nr == row {
+                            horizontal_passages += 1;
+                        } else {
+                            vertical_passages += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Every passage either joins two areas or closes a loop
+        let mut areas = UnionFind::new((width * height) as usize);
+        let joins = passages.iter().filter(|&&(a, b)| areas.union(a, b)).count();
+        let components = cells - joins;
+        let loops = (passages.len() - joins) as u32;
+
+        let graph = self.passage_graph(true);
+        let corridor_count = graph.edges.len() as u32;
+        let corridor_total: u32 = graph.edges.iter().map(|&(_, _, length)| length).sum();
+        let corridor_longest = graph.edges.iter().map(|&(_, _, length)| length).max().unwrap_or(0);
+
+        let (solution_length, tortuosity) = match self.solution_ends() {
+            Some(((r1, c1), (r2, c2))) => {
+                let length = self.distances_from(r1, c1)[index(r2, c2)];
+                let straight = (r1 as i64 - r2 as i64).abs() + (c1 as i64 - c2 as i64).abs();
+                let tortuosity = length.filter(|_| straight > 0).map(|length| length as f64 / straight as f64);
+                (length, tortuosity)
+            }
+            None => (None, None),
+        };
+
+        MazeMetrics {
+            cells: cells as u32,
+            degree_counts,
+            corridor_count,
+            corridor_mean: ratio(corridor_total, corridor_count),
+            corridor_longest,
+            solution_length,
+            tortuosity,
+            components: components as u32,
+            loops,
+            horizontal_passages,
+            vertical_passages,
+        }
+    }
+}
+
+impl Maze {
+    /// Moves needed to reach every cell from `(row, col)`, indexed like
+    /// `get_cells`. Unreachable and disabled cells are `None`.
+    pub fn distances_from(&self, row: u32, col: u32) -> Vec<Option<u32>> {
+        let width = self.width();
+        let mut distances = vec![None; (width * self.height()) as usize];
+        if !self.is_enabled(row, col) {
+            return distances;
+        }
+
+        distances[(row * width + col) as usize] = Some(0);
+        let mut queue = VecDeque::from(vec![(row, col)]);
+        while let Some((r, c)) = queue.pop_front() {
+            let distance = distances[(r * width + c) as usize].unwrap();
+            for (nr, nc) in self.enabled_passages(r, c) {
+                let next = &mut distances[(nr * width + nc) as usize];
+                if next.is_none() {
+                    *next = Some(distance + 1);
+                    queue.push_back((nr, nc));
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Independent loops in the passage graph, the count `metrics` reports
+    /// without walking corridors or solving the maze
+    pub fn loop_count(&self) -> u32 {
+        let width = self.width();
+        let mut passages = HashSet::new();
+        for row in 0..self.height() {
+            for col in 0..width {
+                if self.is_enabled(row, col) {
+                    for (nr, nc) in self.enabled_passages(row, col) {
+                        let (a, b) = ((row * width + col) as usize, (nr * width + nc) as usize);
+                        passages.insert((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+
+        let mut areas = UnionFind::new((width * self.height()) as usize);
+        passages.iter().filter(|&&(a, b)| !areas.union(a, b)).count() as u32
+    }
+
+    fn enabled_passages(&self, row: u32, col: u32) -> Vec<(u32, u32)> {
+        self.passages(row, col)
+            .into_iter()
+            .filter(|&(nr, nc)| self.is_enabled(nr, nc))
+            .collect()
+    }
+
+    /// The cells inside the entrance and exit, the start and end cells, or
+    /// opposite corners without either
+    fn solution_ends(&self) -> Option<((u32, u32), (u32, u32))> {
+        if let (Some((r1, c1, _)), Some((r2, c2, _))) =
+            (self.get_opening(Opening::Entrance), self.get_opening(Opening::Exit))
+        {
+            return Some(((r1, c1), (r2, c2)));
+        }
+
+        let find = |cell_type: CellType| {
+            (0..self.height())
+                .flat_map(|row| (0..self.width()).map(move |col| (row, col)))
+                .find(|&(row, col)| self.get_cell(row, col).get_type() == cell_type)
+        };
+
+        match (find(CellType::Start), find(CellType::End)) {
+            (Some(start), Some(end)) => Some((start, end)),
+            _ if self.width() > 0 && self.height() > 0 => {
+                Some(((0, 0), (self.height() - 1, self.width() - 1)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn ratio(part: u32, whole: u32) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cell::{Cell, CellType, WALL_MASK},
+        generators::MazeBuilder
+    };
+
+    #[test]
+    fn perfect_maze_has_no_loops() {
+        let (_, maze) = MazeBuilder::generated("prims", 3, &Maze::new(8, 8));
+
+        let metrics = maze.metrics();
+        assert_eq!(metrics.cells(), 64);
+        assert_eq!(metrics.components(), 1);
+        assert_eq!(metrics.loops(), 0);
+        assert_eq!(metrics.degree_counts().iter().sum::<u32>(), 64);
+        assert!(metrics.dead_ends() > 0);
+        assert!(metrics.solution_length().unwrap() >= 14);
+        assert!(metrics.tortuosity().unwrap() >= 1.0);
+
+        // Without walls every cell is open to its neighbours
+        let open = Maze::new(3, 3).metrics();
+        assert_eq!(open.loops(), 4);
+        assert_eq!(Maze::new(3, 3).loop_count(), 4);
+        assert_eq!(open.solution_length(), Some(4));
+        assert_eq!(open.horizontal_bias(), 0.5);
+    }
+
+    #[test]
+    fn walled_cells_are_separate_components() {
+        let mut maze = Maze::new(120, 100);
+        let mut cell = Cell::new(CellType::Default);
+        cell.add_wall(WALL_MASK);
+        for row in 0..100 {
+            for col in 0..120 {
+                maze.set_cell(row, col, cell);
+            }
+        }
+
+        let metrics = maze.metrics();
+        assert_eq!(metrics.components(), 12000);
+        assert_eq!(metrics.loops(), 0);
+    }
+}
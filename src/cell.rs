@@ -17,17 +17,7 @@ pub struct Cell {
 impl Cell {
     /// Get the cell type
     pub fn get_type(&self) -> CellType {
-        match self.value & TYPE_MASK {
-            0x00 => CellType::Default,
-            0x10 => CellType::Start,
-            0x20 => CellType::End,
-            0x30 => CellType::Path,
-            0x40 => CellType::Visited,
-            0x50 => CellType::LookingAt,
-            0x60 => CellType::Current,
-            0x70 => CellType::Changing,
-            _ => CellType::Default, // fallback
-        }
+        CellType::from_bits(self.value)
     }
 
 
@@ -95,4 +85,54 @@ pub enum CellType {
     LookingAt = 80,  // 5 << 4
     Current   = 96,  // 6 << 4
     Changing  = 112, // 7 << 4
+    Disabled  = 128, // 8 << 4
+}
+
+impl CellType {
+    /// Decode the type stored in the upper nibble of a raw cell value
+    pub fn from_bits(value: u8) -> CellType {
+        match value & TYPE_MASK {
+            0x00 => CellType::Default,
+            0x10 => CellType::Start,
+            0x20 => CellType::End,
+            0x30 => CellType::Path,
+            0x40 => CellType::Visited,
+            0x50 => CellType::LookingAt,
+            0x60 => CellType::Current,
+            0x70 => CellType::Changing,
+            0x80 => CellType::Disabled,
+            _ => CellType::Default, // fallback
+        }
+    }
+
+    /// Stable lowercase name used by the file formats
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellType::Default => "default",
+            CellType::Start => "start",
+            CellType::End => "end",
+            CellType::Path => "path",
+            CellType::Visited => "visited",
+            CellType::LookingAt => "looking_at",
+            CellType::Current => "current",
+            CellType::Changing => "changing",
+            CellType::Disabled => "disabled",
+        }
+    }
+
+    /// Inverse of `CellType::name`
+    pub fn from_name(name: &str) -> Option<CellType> {
+        match name {
+            "default" => Some(CellType::Default),
+            "start" => Some(CellType::Start),
+            "end" => Some(CellType::End),
+            "path" => Some(CellType::Path),
+            "visited" => Some(CellType::Visited),
+            "looking_at" => Some(CellType::LookingAt),
+            "current" => Some(CellType::Current),
+            "changing" => Some(CellType::Changing),
+            "disabled" => Some(CellType::Disabled),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,189 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    grid::{GridCell, GridMaze, Link, Topology},
+    utils
+};
+
+// Sides of a triangle. The base is the bottom edge of an upward triangle and
+// the top edge of a downward one.
+pub const DELTA_BASE: u8 = 0;
+pub const DELTA_E: u8 = 1;
+pub const DELTA_W: u8 = 2;
+
+const HALF_SQRT_3: f64 = 0.866_025_403_784_438_6;
+
+/// A maze of triangles in rows, alternating between pointing up and down.
+/// The cell at `(0, 0)` points up.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct DeltaMaze {
+    width: u32,
+    height: u32,
+    cells: Vec<GridCell>,
+}
+
+#[wasm_bindgen]
+impl DeltaMaze {
+    pub fn new(width: u32, height: u32) -> DeltaMaze {
+        utils::set_panic_hook();
+
+        DeltaMaze {
+            width,
+            height,
+            cells: vec![GridCell::new(CellType::Default); (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pointer to the `u16` cell values, row by row
+    pub fn cells(&self) -> *const GridCell {
+        self.cells.as_ptr()
+    }
+
+    pub fn points_up(&self, row: u32, col: u32) -> bool {
+        (row + col).is_multiple_of(2)
+    }
+
+    /// The three corners of a cell with edge length `size` as
+    /// `[x0, y0, x1, y1, x2, y2]`. Side `i` runs from corner `i` to corner
+    /// `i + 1`, so the base always comes first.
+    pub fn cell_vertices(&self, row: u32, col: u32, size: f64) -> Vec<f64> {
+        let left = col as f64 * size / 2.0;
+        let right = left + size;
+        let apex = left + size / 2.0;
+        let top = row as f64 * size * HALF_SQRT_3;
+        let bottom = top + size * HALF_SQRT_3;
+
+        if self.points_up(row, col) {
+            vec![left, bottom, right, bottom, apex, top]
+        } else {
+            vec![left, top, right, top, apex, bottom]
+        }
+    }
+
+    /// Width of the canvas needed to draw the maze
+    pub fn pixel_width(&self, size: f64) -> f64 {
+        (self.width + 1) as f64 * size / 2.0
+    }
+
+    /// Height of the canvas needed to draw the maze
+    pub fn pixel_height(&self, size: f64) -> f64 {
+        self.height as f64 * size * HALF_SQRT_3
+    }
+
+    fn get_index(&self, row: u32, col: u32) -> usize {
+        assert!(row < self.height, "Row out of bounds: {} >= {}", row, self.height);
+        assert!(col < self.width, "Column out of bounds: {} >= {}", col, self.width);
+        (row * self.width + col) as usize
+    }
+}
+
+impl DeltaMaze {
+    pub fn get_cell(&self, row: u32, col: u32) -> GridCell {
+        self.cells[self.get_index(row, col)]
+    }
+
+    pub fn set_cell(&mut self, row: u32, col: u32, cell: GridCell) {
+        let idx = self.get_index(row, col);
+        self.cells[idx] = cell;
+    }
+
+    /// Row and column of the cell across `side`, if it is inside the maze
+    pub fn neighbor(&self, row: u32, col: u32, side: u8) -> Option<(u32, u32)> {
+        let (nr, nc) = match side {
+            DELTA_BASE if self.points_up(row, col) => (row as i64 + 1, col as i64),
+            DELTA_BASE => (row as i64 - 1, col as i64),
+            DELTA_E => (row as i64, col as i64 + 1),
+            DELTA_W => (row as i64, col as i64 - 1),
+            _ => return None,
+        };
+
+        if nr < 0 || nc < 0 || nr >= self.height as i64 || nc >= self.width as i64 {
+            return None;
+        }
+        Some((nr as u32, nc as u32))
+    }
+}
+
+impl Topology for DeltaMaze {
+    fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn side_count(&self, _index: usize) -> u8 {
+        3
+    }
+
+    fn links(&self, index: usize) -> Vec<Link> {
+        let row = index as u32 / self.width;
+        let col = index as u32 % self.width;
+
+        [(DELTA_BASE, DELTA_BASE), (DELTA_E, DELTA_W), (DELTA_W, DELTA_E)]
+            .iter()
+            .filter_map(|&(side, back)| {
+                self.neighbor(row, col, side).map(|(nr, nc)| Link {
+                    side,
+                    neighbor: (nr * self.width + nc) as usize,
+                    back,
+                })
+            })
+            .collect()
+    }
+}
+
+impl GridMaze for DeltaMaze {
+    fn grid_cells(&self) -> &[GridCell] {
+        &self.cells
+    }
+
+    fn set_grid_cell(&mut self, index: usize, cell: GridCell) {
+        self.cells[index] = cell;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generators::assert_generates_spanning_trees, grid::assert_links_match};
+
+    #[test]
+    fn links_triangles_along_their_sides() {
+        let maze = DeltaMaze::new(5, 3);
+        assert!(maze.points_up(0, 0) && !maze.points_up(0, 1) && !maze.points_up(1, 0));
+        assert_eq!(maze.links(5 + 2).len(), 3);
+        // Top row triangles pointing down have nothing across their base
+        assert_eq!(maze.neighbor(0, 0, DELTA_BASE), Some((1, 0)));
+        assert_eq!(maze.neighbor(0, 1, DELTA_BASE), None);
+        assert_eq!(maze.links(1).len(), 2);
+        assert_links_match(&maze);
+    }
+
+    #[test]
+    fn neighbours_share_a_side() {
+        let maze = DeltaMaze::new(3, 2);
+        let side = |row: u32, col: u32, side: u8| {
+            let v = maze.cell_vertices(row, col, 10.0);
+            let (i, j) = (2 * side as usize, (2 * side as usize + 2) % 6);
+            let mut ends = [(v[i], v[i + 1]), (v[j], v[j + 1])];
+            ends.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            ends
+        };
+        assert_eq!(side(0, 0, DELTA_E), side(0, 1, DELTA_W));
+        assert_eq!(side(0, 0, DELTA_BASE), side(1, 0, DELTA_BASE));
+        assert_eq!(side(1, 1, DELTA_E), side(1, 2, DELTA_W));
+    }
+
+    #[test]
+    fn every_generator_carves_a_spanning_tree() {
+        assert_generates_spanning_trees(&DeltaMaze::new(9, 5));
+    }
+}
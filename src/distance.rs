@@ -0,0 +1,142 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    generators::MazeBuilder,
+    maze::{Maze, MazeChange}
+};
+
+/// Marks cells nobody can reach in `DistanceMap::distances`
+pub const UNREACHABLE: u32 = u32::MAX;
+
+/// Moves from one source cell to every other cell through open passages
+#[wasm_bindgen]
+pub struct DistanceMap {
+    width: u32,
+    source: (u32, u32),
+    distances: Vec<u32>,
+    max_distance: u32,
+}
+
+#[wasm_bindgen]
+impl DistanceMap {
+    /// Pointer to one `u32` per cell, row by row, for a `Uint32Array` view.
+    /// Unreachable cells hold `UNREACHABLE` (0xFFFFFFFF).
+    pub fn distances(&self) -> *const u32 {
+        self.distances.as_ptr()
+    }
+
+    pub fn distance(&self, row: u32, col: u32) -> Option<u32> {
+        Some(self.distances[(row * self.width + col) as usize]).filter(|&d| d != UNREACHABLE)
+    }
+
+    /// Largest distance to a reachable cell, handy to normalise a heatmap
+    pub fn max_distance(&self) -> u32 {
+        self.max_distance
+    }
+
+    /// `[row, col]` of a reachable cell furthest from the source, empty if
+    /// the source is disabled
+    pub fn farthest(&self) -> Vec<u32> {
+        match self.distances.iter().position(|&d| d == self.max_distance) {
+            Some(index) => vec![index as u32 / self.width, index as u32 % self.width],
+            None => Vec::new(),
+        }
+    }
+
+    pub fn source(&self) -> Vec<u32> {
+        vec![self.source.0, self.source.1]
+    }
+
+    /// A builder that plays the flood on `maze` one wave at a time: each
+    /// step marks the cells at the next distance `Current` and turns the
+    /// previous wave `Visited`. Start and end cells keep their type.
+    pub fn wave_trace(&self, maze: &Maze) -> MazeBuilder {
+        let mut waves = vec![Vec::new(); self.max_distance as usize + 1];
+        for (i, &distance) in self.distances.iter().enumerate() {
+            if distance != UNREACHABLE {
+                waves[distance as usize].push((i as u32 / self.width, i as u32 % self.width));
+            }
+        }
+
+        let mut maze = maze.clone();
+        let mut steps = Vec::with_capacity(waves.len() + 1);
+        let mut previous: &[(u32, u32)] = &[];
+        for wave in waves.iter().map(Vec::as_slice).chain(std::iter::once(&[][..])) {
+            let mut step = Vec::new();
+            for &(row, col) in previous {
+                set_type(&mut maze, row, col, CellType::Visited, &mut step);
+            }
+            for &(row, col) in wave {
+                set_type(&mut maze, row, col, CellType::Current, &mut step);
+            }
+            steps.push(step);
+            previous = wave;
+        }
+
+        MazeBuilder::from_recording("distance_flood", &[], None, steps, Vec::new())
+    }
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Flood the maze from `(row, col)`
+    pub fn distance_map(&self, row: u32, col: u32) -> DistanceMap {
+        let distances: Vec<u32> = self
+            .distances_from(row, col)
+            .into_iter()
+            .map(|distance| distance.unwrap_or(UNREACHABLE))
+            .collect();
+        let max_distance = distances.iter().copied().filter(|&d| d != UNREACHABLE).max().unwrap_or(0);
+
+        DistanceMap {
+            width: self.width(),
+            source: (row, col),
+            distances,
+            max_distance,
+        }
+    }
+}
+
+fn set_type(maze: &mut Maze, row: u32, col: u32, cell_type: CellType, step: &mut Vec<MazeChange>) {
+    let old = maze.get_cell(row, col);
+    if matches!(old.get_type(), CellType::Start | CellType::End) || old.get_type() == cell_type {
+        return;
+    }
+
+    let mut new = old;
+    new.set_type(cell_type);
+    maze.set_cell(row, col, new);
+    step.push(MazeChange { row, col, old, new });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{WALL_E, WALL_W};
+
+    #[test]
+    fn floods_open_passages_in_waves() {
+        // A wall down the middle of a 2x2 maze, open only along the bottom
+        let mut maze = Maze::new(2, 2);
+        maze.get_cell_mut(0, 0).add_wall(WALL_E);
+        maze.get_cell_mut(0, 1).add_wall(WALL_W);
+
+        let map = maze.distance_map(0, 0);
+        assert_eq!(map.distance(0, 1), Some(3));
+        assert_eq!(map.max_distance(), 3);
+        assert_eq!(map.farthest(), vec![0, 1]);
+
+        let mut builder = map.wave_trace(&maze);
+        assert_eq!(builder.total_steps(), 5);
+
+        // A flood can't be generated again, only replayed
+        builder.generate_all(&maze);
+        assert_eq!(builder.generator_name(), "distance_flood");
+        assert_eq!(builder.total_steps(), 5);
+        builder.step_forward(&mut maze);
+        builder.step_forward(&mut maze);
+        assert_eq!(maze.get_cell(0, 0).get_type(), CellType::Visited);
+        assert_eq!(maze.get_cell(1, 0).get_type(), CellType::Current);
+    }
+}
@@ -0,0 +1,247 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{Cell, CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    formats::FormatError,
+    maze::{Maze, Opening}
+};
+
+/// Text mazes come in two styles, told apart by the first character:
+///
+/// ```text
+/// +--+--+      #####
+/// |S    |      #S  #
+/// +--+  +      ### #
+/// |E    |      #E  #
+/// +--+--+      #####
+/// ```
+///
+/// In the wall style, the `+` of the first line fix the column positions
+/// and every cell may be any width. Cells hold `S`, `E`, `.` for a path or
+/// `#` for a disabled cell. A gap in the outside wall of the `S` or `E`
+/// cell is read as the entrance or exit; any other gap stays a plain hole
+/// in the boundary. The `:` that `to_text` draws where a weave passage
+/// tunnels under a crossing reads as an open edge.
+///
+/// In the block style every character is a cell: `#` is a wall cell
+/// (`CellType::Changing`, as `toggle_cell` makes them), space or `.` is
+/// open.
+#[wasm_bindgen]
+impl Maze {
+    /// Parse a hand-drawn maze. Throws the line and column of the first
+    /// character that doesn't fit.
+    #[wasm_bindgen(js_name = "from_ascii")]
+    pub fn from_ascii_js(text: &str) -> Result<Maze, JsValue> {
+        Maze::from_ascii(text).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Maze {
+    pub fn from_ascii(text: &str) -> Result<Maze, FormatError> {
+        let lines: Vec<(usize, Vec<char>)> = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end().chars().collect()))
+            .collect();
+
+        // Blank lines around the drawing don't count
+        let first = lines.iter().position(|(_, line)| !line.is_empty());
+        let last = lines.iter().rposition(|(_, line)| !line.is_empty());
+        let lines = match (first, last) {
+            (Some(first), Some(last)) => &lines[first..=last],
+            _ => return Err(FormatError::Syntax("no maze in the text".to_string())),
+        };
+
+        if lines[0].1[0] == '+' {
+            parse_walls(lines)
+        } else {
+            parse_blocks(lines)
+        }
+    }
+}
+
+fn error(line: usize, column: usize, message: &str) -> FormatError {
+    FormatError::Parse { line, column, message: message.to_string() }
+}
+
+fn parse_walls(lines: &[(usize, Vec<char>)]) -> Result<Maze, FormatError> {
+    let corners: Vec<usize> = lines[0].1
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == '+')
+        .map(|(i, _)| i)
+        .collect();
+    if corners.len() < 2 {
+        return Err(error(lines[0].0, 1, "the first line needs a '+' on each side of every cell"));
+    }
+    if lines.len() < 3 || lines.len().is_multiple_of(2) {
+        let (line, _) = lines[lines.len() - 1];
+        return Err(error(line, 1, "expected a line of corners after every row of cells"));
+    }
+
+    let width = corners.len() as u32 - 1;
+    let height = lines.len() as u32 / 2;
+    let last_column = *corners.last().unwrap();
+    let mut maze = Maze::new(width, height);
+
+    for (y, (number, line)) in lines.iter().enumerate() {
+        let at = |column: usize| line.get(column).copied().unwrap_or(' ');
+        if let Some(extra) = (last_column + 1..line.len()).find(|&column| at(column) != ' ') {
+            return Err(error(*number, extra + 1, "text past the right edge of the maze"));
+        }
+
+        let y = y as u32;
+        if y.is_multiple_of(2) {
+            // Corners and the horizontal walls between them
+            for &column in &corners {
+                if !matches!(at(column), '+' | '-' | '|' | ' ') {
+                    return Err(error(*number, column + 1, "expected a corner"));
+                }
+            }
+            for (x, pair) in corners.windows(2).enumerate() {
+                let span = pair[0] + 1..pair[1];
+                let closed = match span.clone().find(|&column| at(column) != '-') {
+                    None => true,
+                    Some(_) => {
+                        if let Some(column) = span.clone().find(|&column| !matches!(at(column), ' ' | ':')) {
+                            return Err(error(*number, column + 1, "expected a wall of '-' or an opening of spaces"));
+                        }
+                        false
+                    }
+                };
+
+                if closed {
+                    close_edge(&mut maze, y / 2, x as u32, WALL_N);
+                }
+            }
+        } else {
+            // Vertical walls and the cells between them
+            let row = y / 2;
+            for (x, &column) in corners.iter().enumerate() {
+                match at(column) {
+                    '|' => close_edge(&mut maze, row, x as u32, WALL_W),
+                    ' ' | ':' => {}
+                    _ => return Err(error(*number, column + 1, "expected '|' or ' ' for a wall")),
+                }
+            }
+
+            for (x, pair) in corners.windows(2).enumerate() {
+                let mut cell_type = CellType::Default;
+                for column in pair[0] + 1..pair[1] {
+                    let marked = match at(column) {
+                        ' ' => continue,
+                        'S' | 's' => CellType::Start,
+                        'E' | 'e' => CellType::End,
+                        '.' => CellType::Path,
+                        '#' => CellType::Disabled,
+                        _ => return Err(error(*number, column + 1, "unknown cell marker")),
+                    };
+                    if cell_type != CellType::Default && cell_type != marked {
+                        return Err(error(*number, column + 1, "a cell can only have one marker"));
+                    }
+                    cell_type = marked;
+                }
+                maze.get_cell_mut(row, x as u32).set_type(cell_type);
+            }
+        }
+    }
+
+    for (opening, marker) in [(Opening::Entrance, CellType::Start), (Opening::Exit, CellType::End)] {
+        let cell = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .find(|&(row, col)| maze.get_cell(row, col).get_type() == marker);
+        if let Some((row, col)) = cell {
+            let gap = [WALL_N, WALL_W, WALL_S, WALL_E]
+                .iter()
+                .copied()
+                .find(|&wall| maze.faces_outside(row, col, wall) && !maze.get_cell(row, col).has_wall(wall));
+            if let Some(wall) = gap {
+                maze.set_opening(opening, row, col, wall);
+            }
+        }
+    }
+
+    Ok(maze)
+}
+
+/// Close the edge on the `wall` side of a cell, and the matching side of
+/// the neighbour for inner edges. The bottom and right boundary map onto
+/// the last row and column.
+fn close_edge(maze: &mut Maze, row: u32, col: u32, wall: u8) {
+    let (row, col, wall) = match wall {
+        WALL_N if row == maze.height() => (row - 1, col, WALL_S),
+        WALL_W if col == maze.width() => (row, col - 1, WALL_E),
+        _ => (row, col, wall),
+    };
+    maze.get_cell_mut(row, col).add_wall(wall);
+
+    let (back, dr, dc) = match wall {
+        WALL_N => (WALL_S, -1, 0),
+        WALL_S => (WALL_N, 1, 0),
+        WALL_W => (WALL_E, 0, -1),
+        _ => (WALL_W, 0, 1),
+    };
+    if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+        maze.get_cell_mut(nr, nc).add_wall(back);
+    }
+}
+
+fn parse_blocks(lines: &[(usize, Vec<char>)]) -> Result<Maze, FormatError> {
+    // Short lines are open to the right, hand-drawn mazes lose trailing spaces
+    let width = lines.iter().map(|(_, line)| line.len()).max().unwrap() as u32;
+    let mut maze = Maze::new(width, lines.len() as u32);
+
+    for (row, (number, line)) in lines.iter().enumerate() {
+        for (col, &c) in line.iter().enumerate() {
+            let cell_type = match c {
+                '#' => CellType::Changing,
+                ' ' | '.' => CellType::Default,
+                'S' | 's' => CellType::Start,
+                'E' | 'e' => CellType::End,
+                _ => return Err(error(*number, col + 1, "expected '#', ' ', 'S' or 'E'")),
+            };
+            maze.set_cell(row as u32, col as u32, Cell::new(cell_type));
+        }
+    }
+
+    Ok(maze)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_wall_and_block_styles() {
+        let text = "+--+--+--+\n|S    |  |\n+--+  +  +\n|E  .     \n+--+--+--+\n";
+        let maze = Maze::from_ascii(text).unwrap();
+        assert_eq!((maze.width(), maze.height()), (3, 2));
+        assert_eq!(maze.get_cell(1, 1).get_type(), CellType::Path);
+        assert!(maze.get_cell(0, 1).has_wall(WALL_E) && maze.get_cell(0, 2).has_wall(WALL_W));
+        assert!(!maze.get_cell(1, 2).has_wall(WALL_E));
+
+        let blocks = Maze::from_ascii("###\n#S \n# E\n").unwrap();
+        assert_eq!(blocks.get_cell(0, 1).get_type(), CellType::Changing);
+        assert_eq!(blocks.get_cell(2, 2).get_type(), CellType::End);
+    }
+
+    #[test]
+    fn reads_openings_next_to_start_and_end() {
+        let maze = Maze::from_ascii("+  +--+\n|S    |\n+--+  +\n E    |\n+--+--+\n").unwrap();
+        assert_eq!(maze.get_opening(Opening::Entrance), Some((0, 0, WALL_N)));
+        assert_eq!(maze.get_opening(Opening::Exit), Some((1, 0, WALL_W)));
+        assert!(maze.validate().is_perfect());
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        assert_eq!(
+            Maze::from_ascii("+--+\n|x |\n+--+").err(),
+            Some(error(2, 2, "unknown cell marker"))
+        );
+        assert_eq!(
+            Maze::from_ascii("\n##\n#?").err(),
+            Some(error(3, 2, "expected '#', ' ', 'S' or 'E'"))
+        );
+    }
+}
@@ -0,0 +1,427 @@
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    formats::{cell_count, check_walls, FormatError},
+    maze::{Maze, Opening}
+};
+
+pub const BINARY_MAGIC: &[u8; 4] = b"MZWB";
+/// Version 2 adds the openings section
+pub const BINARY_VERSION: u8 = 2;
+
+// Header flags
+const FLAG_WRAP_HORIZONTAL: u8 = 0b0001;
+const FLAG_WRAP_VERTICAL: u8 = 0b0010;
+const FLAG_TYPES: u8 = 0b0100;
+const FLAG_CROSSINGS: u8 = 0b1000;
+const FLAG_OPENINGS: u8 = 0b1_0000;
+const KNOWN_FLAGS: u8 = FLAG_WRAP_HORIZONTAL | FLAG_WRAP_VERTICAL | FLAG_TYPES | FLAG_CROSSINGS | FLAG_OPENINGS;
+
+// Set on a crossing index when the tunnel runs east-west
+const CROSSING_HORIZONTAL: u32 = 1 << 31;
+
+/// Layout, all integers little endian:
+///
+/// ```text
+/// magic "MZWB" | version u8 | flags u8 | width u32 | height u32
+/// walls       2 bits per cell, east then south, packed from the lowest bit
+/// boundary    north walls of row 0 then west walls of column 0, one bit
+///             each, only for the edges that don't wrap
+/// types       4 bits per cell (FLAG_TYPES)
+/// crossings   count u32, then one u32 cell index each (FLAG_CROSSINGS)
+/// openings    entrance then exit, each a wall u8 (0 for none) followed
+///             by its u32 cell index (FLAG_OPENINGS)
+/// crc32       of everything before it
+/// ```
+///
+/// Every section starts on a byte boundary. North and west walls of inner
+/// cells are taken from the neighbour, which is why a weave crossing stores
+/// the direction of its tunnel instead.
+#[wasm_bindgen]
+impl Maze {
+    /// Serialize the maze to the compact binary format. Cell types are
+    /// only stored when `include_types` is set.
+    pub fn to_bytes(&self, include_types: bool) -> Vec<u8> {
+        let (width, height) = (self.width(), self.height());
+        let crossings: Vec<u32> = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.is_under_crossing(row, col))
+            .map(|(row, col)| {
+                let index = row * width + col;
+                if self.get_cell(row, col).has_wall(WALL_E) { index | CROSSING_HORIZONTAL } else { index }
+            })
+            .collect();
+
+        let mut flags = 0;
+        if self.wraps_horizontally() {
+            flags |= FLAG_WRAP_HORIZONTAL;
+        }
+        if self.wraps_vertically() {
+            flags |= FLAG_WRAP_VERTICAL;
+        }
+        if include_types {
+            flags |= FLAG_TYPES;
+        }
+        if !crossings.is_empty() {
+            flags |= FLAG_CROSSINGS;
+        }
+        let openings = [self.get_opening(Opening::Entrance), self.get_opening(Opening::Exit)];
+        if openings.iter().any(Option::is_some) {
+            flags |= FLAG_OPENINGS;
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.push(BINARY_VERSION);
+        bytes.push(flags);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+
+        // A wall only counts as closed if both cells agree, so tunnels are
+        // stored as the open passage they are from the outside. On the edge
+        // of a mask the enabled side decides, like on the outer boundary.
+        let mut walls = BitWriter::default();
+        for row in 0..height {
+            for col in 0..width {
+                let cell = self.get_cell(row, col);
+                for &(wall, back, dr, dc) in &[(WALL_E, WALL_W, 0, 1), (WALL_S, WALL_N, 1, 0)] {
+                    let closed = match self.neighbor(row, col, dr, dc) {
+                        Some((nr, nc)) => {
+                            let there = self.get_cell(nr, nc).has_wall(back);
+                            match (self.is_enabled(row, col), self.is_enabled(nr, nc)) {
+                                (true, false) => cell.has_wall(wall),
+                                (false, true) => there,
+                                _ => cell.has_wall(wall) && there,
+                            }
+                        }
+                        None => cell.has_wall(wall),
+                    };
+                    walls.push(closed as u8, 1);
+                }
+            }
+        }
+        bytes.extend(walls.finish());
+
+        let mut boundary = BitWriter::default();
+        if !self.wraps_vertically() {
+            for col in 0..width {
+                boundary.push(self.get_cell(0, col).has_wall(WALL_N) as u8, 1);
+            }
+        }
+        if !self.wraps_horizontally() {
+            for row in 0..height {
+                boundary.push(self.get_cell(row, 0).has_wall(WALL_W) as u8, 1);
+            }
+        }
+        bytes.extend(boundary.finish());
+
+        if include_types {
+            let mut types = BitWriter::default();
+            for cell in self.get_cells() {
+                types.push(cell.get_type() as u8 >> 4, 4);
+            }
+            bytes.extend(types.finish());
+        }
+
+        if !crossings.is_empty() {
+            bytes.extend_from_slice(&(crossings.len() as u32).to_le_bytes());
+            for crossing in crossings {
+                bytes.extend_from_slice(&crossing.to_le_bytes());
+            }
+        }
+
+        if flags & FLAG_OPENINGS != 0 {
+            for opening in openings {
+                match opening {
+                    Some((row, col, wall)) => {
+                        bytes.push(wall);
+                        bytes.extend_from_slice(&(row * width + col).to_le_bytes());
+                    }
+                    None => bytes.push(0),
+                }
+            }
+        }
+
+        let crc = crc32(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Load a maze saved with `to_bytes`. Throws a description of the first
+    /// problem found if the data is damaged.
+    #[wasm_bindgen(js_name = "from_bytes")]
+    pub fn from_bytes_js(bytes: &[u8]) -> Result<Maze, JsValue> {
+        Maze::from_bytes(bytes).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Maze {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Maze, FormatError> {
+        if bytes.len() < 4 || &bytes[..4] != BINARY_MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        if bytes.len() < 18 {
+            return Err(FormatError::Truncated);
+        }
+
+        let (body, crc) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes(crc.try_into().unwrap());
+        let found = crc32(body);
+        if expected != found {
+            return Err(FormatError::ChecksumMismatch { expected, found });
+        }
+
+        let version = body[4];
+        if version > BINARY_VERSION {
+            return Err(FormatError::UnsupportedVersion(version as u32));
+        }
+        let flags = body[5];
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(FormatError::Syntax(format!("unknown flags {:#010b}", flags & !KNOWN_FLAGS)));
+        }
+        let width = u32::from_le_bytes(body[6..10].try_into().unwrap());
+        let height = u32::from_le_bytes(body[10..14].try_into().unwrap());
+        let count = cell_count(width, height)?;
+        // Don't trust the dimensions with an allocation before the walls fit
+        if body.len() - 14 < (2 * count).div_ceil(8) {
+            return Err(FormatError::Truncated);
+        }
+
+        let mut maze = Maze::new(width, height);
+        maze.set_wrap(flags & FLAG_WRAP_HORIZONTAL != 0, flags & FLAG_WRAP_VERTICAL != 0);
+
+        let mut reader = BitReader::new(&body[14..]);
+        for row in 0..height {
+            for col in 0..width {
+                let east = reader.read(1)? != 0;
+                let south = reader.read(1)? != 0;
+                if east {
+                    set_wall_pair(&mut maze, row, col, WALL_E, WALL_W, 0, 1);
+                }
+                if south {
+                    set_wall_pair(&mut maze, row, col, WALL_S, WALL_N, 1, 0);
+                }
+            }
+        }
+        reader.align();
+
+        if !maze.wraps_vertically() {
+            for col in 0..width {
+                if reader.read(1)? != 0 {
+                    maze.get_cell_mut(0, col).add_wall(WALL_N);
+                }
+            }
+        }
+        if !maze.wraps_horizontally() {
+            for row in 0..height {
+                if reader.read(1)? != 0 {
+                    maze.get_cell_mut(row, 0).add_wall(WALL_W);
+                }
+            }
+        }
+        reader.align();
+
+        if flags & FLAG_TYPES != 0 {
+            for row in 0..height {
+                for col in 0..width {
+                    let cell_type = CellType::from_bits((reader.read(4)? as u8) << 4);
+                    maze.get_cell_mut(row, col).set_type(cell_type);
+                }
+            }
+            reader.align();
+        }
+
+        if flags & FLAG_CROSSINGS != 0 {
+            let crossings = reader.read(32)?;
+            for _ in 0..crossings {
+                let entry = reader.read(32)?;
+                let index = entry & !CROSSING_HORIZONTAL;
+                if index as usize >= count {
+                    return Err(FormatError::Syntax(format!("crossing {} is outside the maze", index)));
+                }
+
+                let (row, col) = (index / width, index % width);
+                let tunnel = if entry & CROSSING_HORIZONTAL != 0 { WALL_E | WALL_W } else { WALL_N | WALL_S };
+                maze.get_cell_mut(row, col).add_wall(tunnel);
+                maze.set_under_crossing(row, col, true);
+            }
+        }
+
+        if flags & FLAG_OPENINGS != 0 {
+            for opening in [Opening::Entrance, Opening::Exit] {
+                let wall = reader.read(8)? as u8;
+                if wall == 0 {
+                    continue;
+                }
+                let index = reader.read(32)?;
+                let (row, col) = (index / width.max(1), index % width.max(1));
+                if index as usize >= count || !maze.faces_outside(row, col, wall) {
+                    return Err(FormatError::Syntax(format!("{:?} {} is not on the outside of the maze", opening, index)));
+                }
+                maze.set_opening(opening, row, col, wall);
+            }
+        }
+
+        if !reader.is_done() {
+            return Err(FormatError::Syntax("trailing bytes after the last section".to_string()));
+        }
+
+        check_walls(&maze)?;
+        Ok(maze)
+    }
+}
+
+/// Close the wall between a cell and its neighbour on both sides
+fn set_wall_pair(maze: &mut Maze, row: u32, col: u32, wall: u8, back: u8, dr: i32, dc: i32) {
+    maze.get_cell_mut(row, col).add_wall(wall);
+    if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+        maze.get_cell_mut(nr, nc).add_wall(back);
+    }
+}
+
+/// CRC-32 as used by zlib and PNG
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Packs values into bytes starting at the lowest bit
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u8, width: usize) {
+        for i in 0..width {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value & (1 << i) != 0 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, bits: 0 }
+    }
+
+    fn read(&mut self, width: usize) -> Result<u32, FormatError> {
+        let mut value = 0;
+        for i in 0..width {
+            let byte = self.bytes.get(self.bits / 8).ok_or(FormatError::Truncated)?;
+            if byte & (1 << (self.bits % 8)) != 0 {
+                value |= 1 << i;
+            }
+            self.bits += 1;
+        }
+        Ok(value)
+    }
+
+    /// Skip to the start of the next byte
+    fn align(&mut self) {
+        self.bits = self.bits.div_ceil(8) * 8;
+    }
+
+    fn is_done(&self) -> bool {
+        self.bits.div_ceil(8) >= self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn round_trip_keeps_walls_and_types() {
+        let mut maze = Maze::new(5, 3);
+        for row in 0..3 {
+            for col in 0..5 {
+                let mut cell = Cell::new(if row == col { CellType::Path } else { CellType::Default });
+                cell.add_wall(WALL_N | WALL_E | WALL_S | WALL_W);
+                maze.set_cell(row, col, cell);
+            }
+        }
+        maze.get_cell_mut(1, 1).remove_wall(WALL_E);
+        maze.get_cell_mut(1, 2).remove_wall(WALL_W);
+        maze.get_cell_mut(0, 3).remove_wall(WALL_N);
+        maze.set_opening(Opening::Exit, 2, 4, WALL_E);
+
+        let bytes = maze.to_bytes(true);
+        let loaded = Maze::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.get_cells(), maze.get_cells());
+        assert_eq!(loaded.get_opening(Opening::Exit), Some((2, 4, WALL_E)));
+        assert_eq!(loaded.get_opening(Opening::Entrance), None);
+
+        let walls_only = Maze::from_bytes(&maze.to_bytes(false)).unwrap();
+        assert_eq!(walls_only.get_cell(1, 1).get_type(), CellType::Default);
+        assert_eq!(walls_only.get_cell(1, 1).raw() & 0x0F, maze.get_cell(1, 1).raw() & 0x0F);
+    }
+
+    #[test]
+    fn keeps_walls_on_mask_edges() {
+        // Only the enabled cells wall off the disabled one between them
+        let mut maze = Maze::new(3, 1);
+        maze.apply_mask(&[true, false, true]);
+        maze.get_cell_mut(0, 0).add_wall(WALL_E);
+        maze.get_cell_mut(0, 2).add_wall(WALL_W);
+
+        let loaded = Maze::from_bytes(&maze.to_bytes(true)).unwrap();
+        assert!(loaded.get_cell(0, 0).has_wall(WALL_E));
+        assert!(loaded.get_cell(0, 2).has_wall(WALL_W));
+        assert!(!loaded.is_enabled(0, 1));
+    }
+
+    #[test]
+    fn rejects_damaged_data() {
+        let mut bytes = Maze::new(4, 4).to_bytes(false);
+        bytes[15] ^= 1;
+        assert!(matches!(Maze::from_bytes(&bytes), Err(FormatError::ChecksumMismatch { .. })));
+
+        // A flag from a later version, with a valid checksum
+        let mut bytes = Maze::new(4, 4).to_bytes(false);
+        bytes.truncate(bytes.len() - 4);
+        bytes[5] |= 0b1000_0000;
+        bytes.extend_from_slice(&crc32(&bytes).to_le_bytes());
+        assert!(matches!(Maze::from_bytes(&bytes), Err(FormatError::Syntax(_))));
+        assert_eq!(Maze::from_bytes(b"PNG").err(), Some(FormatError::BadMagic));
+    }
+
+    #[test]
+    fn rejects_dimensions_that_overflow() {
+        // 65536 x 65537 wraps to 65536 cells in 32 bits, and the body has
+        // room for the walls of that many
+        let mut bytes = Maze::new(1, 1).to_bytes(false);
+        bytes.truncate(14);
+        bytes[6..10].copy_from_slice(&65536u32.to_le_bytes());
+        bytes[10..14].copy_from_slice(&65537u32.to_le_bytes());
+        bytes.resize(14 + 2 * 65536 / 8 + 16, 0);
+        bytes.extend_from_slice(&crc32(&bytes).to_le_bytes());
+        assert!(matches!(Maze::from_bytes(&bytes), Err(FormatError::Syntax(_))));
+    }
+}
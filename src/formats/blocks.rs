@@ -0,0 +1,256 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{Cell, CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    formats::FormatError,
+    maze::{Maze, Opening}
+};
+
+/// Type of a wall block, the same one `toggle_cell` paints
+pub const BLOCK_WALL: CellType = CellType::Changing;
+
+/// A block maze spends one cell on every wall: cell `(row, col)` of a wall
+/// maze becomes block `(2 * row + 1, 2 * col + 1)`, the edges between cells
+/// become the blocks between those, and every corner is a wall block.
+#[wasm_bindgen]
+impl Maze {
+    /// The equivalent (2W+1)×(2H+1) block maze. It wraps like this maze,
+    /// the entrance and exit open the gap blocks in the boundary, and weave
+    /// crossings stay on the cell blocks they pass under. Blocks can't pass
+    /// under each other, so the tunnels themselves show as walls.
+    pub fn to_blocks(&self) -> Maze {
+        let (width, height) = (self.width(), self.height());
+        let mut blocks = Maze::new(2 * width + 1, 2 * height + 1);
+        blocks.set_wrap(self.wraps_horizontally(), self.wraps_vertically());
+        for row in 0..blocks.height() {
+            for col in 0..blocks.width() {
+                blocks.set_cell(row, col, Cell::new(BLOCK_WALL));
+            }
+        }
+
+        for row in 0..height {
+            for col in 0..width {
+                let cell = self.get_cell(row, col);
+                let (block_row, block_col) = (2 * row + 1, 2 * col + 1);
+                blocks.set_cell(block_row, block_col, Cell::new(cell.get_type()));
+                blocks.set_under_crossing(block_row, block_col, self.is_under_crossing(row, col));
+
+                // Each cell owns its east and south edge, and the boundary
+                // cells their north or west edge as well
+                let mut edges = vec![(WALL_E, 0, 1), (WALL_S, 1, 0)];
+                if row == 0 {
+                    edges.push((WALL_N, -1, 0));
+                }
+                if col == 0 {
+                    edges.push((WALL_W, 0, -1));
+                }
+
+                for (wall, dr, dc) in edges {
+                    let neighbor = self.neighbor(row, col, dr, dc);
+                    let closed = cell.has_wall(wall) || neighbor.is_some_and(|(nr, nc)| {
+                        self.get_cell(nr, nc).has_wall(opposite(wall))
+                    });
+                    if closed {
+                        continue;
+                    }
+
+                    // Keep a solution path unbroken across the gap
+                    let on_path = cell.get_type() == CellType::Path && neighbor.is_some_and(|(nr, nc)| {
+                        self.get_cell(nr, nc).get_type() == CellType::Path
+                    });
+                    let gap = Cell::new(if on_path { CellType::Path } else { CellType::Default });
+                    let edge_row = (block_row as i32 + dr) as u32;
+                    let edge_col = (block_col as i32 + dc) as u32;
+                    blocks.set_cell(edge_row, edge_col, gap);
+                }
+            }
+        }
+
+        for &opening in &[Opening::Entrance, Opening::Exit] {
+            if let Some((row, col, wall)) = self.get_opening(opening) {
+                let (dr, dc) = offset(wall);
+                let gap_row = (2 * row as i32 + 1 + dr) as u32;
+                let gap_col = (2 * col as i32 + 1 + dc) as u32;
+                if blocks.faces_outside(gap_row, gap_col, wall) {
+                    blocks.set_opening(opening, gap_row, gap_col, wall);
+                }
+            }
+        }
+
+        blocks
+    }
+
+    /// Turn a block maze back into a wall maze. Throws if a corner is open,
+    /// a cell position is a wall block or a weave crossing or opening isn't
+    /// where `to_blocks` puts them, since walls can't express that.
+    #[wasm_bindgen(js_name = "from_blocks")]
+    pub fn from_blocks_js(blocks: &Maze) -> Result<Maze, JsValue> {
+        Maze::from_blocks(blocks).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Maze {
+    pub fn from_blocks(blocks: &Maze) -> Result<Maze, FormatError> {
+        let is_wall = |row: u32, col: u32| blocks.get_cell(row, col).get_type() == BLOCK_WALL;
+        let layout_error = |row, col, message: &str| {
+            Err(FormatError::BlockLayout { row, col, message: message.to_string() })
+        };
+
+        if blocks.width() < 3 || blocks.height() < 3
+            || blocks.width().is_multiple_of(2) || blocks.height().is_multiple_of(2)
+        {
+            return layout_error(blocks.height(), blocks.width(), "a block maze has odd sides of at least 3");
+        }
+
+        for row in (0..blocks.height()).step_by(2) {
+            for col in (0..blocks.width()).step_by(2) {
+                if !is_wall(row, col) {
+                    return layout_error(row, col, "corners between cells have to be walls");
+                }
+            }
+        }
+
+        let mut maze = Maze::new(blocks.width() / 2, blocks.height() / 2);
+        maze.set_wrap(blocks.wraps_horizontally(), blocks.wraps_vertically());
+        for row in 0..blocks.height() {
+            for col in 0..blocks.width() {
+                if blocks.is_under_crossing(row, col) && (row % 2 == 0 || col % 2 == 0) {
+                    return layout_error(row, col, "only a cell position can have a weave crossing");
+                }
+            }
+        }
+
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                let (block_row, block_col) = (2 * row + 1, 2 * col + 1);
+                if is_wall(block_row, block_col) {
+                    return layout_error(block_row, block_col, "a cell position can't be a wall");
+                }
+
+                let mut cell = Cell::new(blocks.get_cell(block_row, block_col).get_type());
+                for &(wall, dr, dc) in &[(WALL_N, -1, 0), (WALL_E, 0, 1), (WALL_S, 1, 0), (WALL_W, 0, -1)] {
+                    if is_wall((block_row as i32 + dr) as u32, (block_col as i32 + dc) as u32) {
+                        cell.add_wall(wall);
+                    }
+                }
+                maze.set_cell(row, col, cell);
+                maze.set_under_crossing(row, col, blocks.is_under_crossing(block_row, block_col));
+            }
+        }
+
+        // A tunnel runs across the walled axis of its crossing, through the
+        // walls the blocks closed on either side
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                if !maze.is_under_crossing(row, col) {
+                    continue;
+                }
+                for &(wall, back) in &[(WALL_N, WALL_S), (WALL_E, WALL_W)] {
+                    let cell = maze.get_cell(row, col);
+                    if !cell.has_wall(wall) || !cell.has_wall(back) {
+                        continue;
+                    }
+                    for &side in &[wall, back] {
+                        let (dr, dc) = offset(side);
+                        if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+                            maze.get_cell_mut(nr, nc).remove_wall(opposite(side));
+                        }
+                    }
+                }
+            }
+        }
+
+        for &opening in &[Opening::Entrance, Opening::Exit] {
+            if let Some((gap_row, gap_col, wall)) = blocks.get_opening(opening) {
+                // The cell block lies one step inwards from the gap
+                let (dr, dc) = offset(wall);
+                let (block_row, block_col) = (gap_row as i32 - dr, gap_col as i32 - dc);
+                let (row, col) = ((block_row - 1) / 2, (block_col - 1) / 2);
+                let on_cell = block_row % 2 == 1 && block_col % 2 == 1
+                    && (row as u32) < maze.height() && (col as u32) < maze.width();
+                if !on_cell || !maze.faces_outside(row as u32, col as u32, wall) {
+                    return layout_error(gap_row, gap_col, "an opening has to lead out of a cell");
+                }
+                maze.set_opening(opening, row as u32, col as u32, wall);
+            }
+        }
+
+        Ok(maze)
+    }
+}
+
+fn offset(wall: u8) -> (i32, i32) {
+    match wall {
+        WALL_N => (-1, 0),
+        WALL_S => (1, 0),
+        WALL_E => (0, 1),
+        _ => (0, -1),
+    }
+}
+
+fn opposite(wall: u8) -> u8 {
+    match wall {
+        WALL_N => WALL_S,
+        WALL_S => WALL_N,
+        WALL_E => WALL_W,
+        _ => WALL_E,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::MazeBuilder;
+
+    #[test]
+    fn round_trip_through_blocks() {
+        let (_, mut maze) = MazeBuilder::generated("prims", 11, &Maze::new(7, 5));
+        maze.get_cell_mut(0, 0).set_type(CellType::Start);
+        maze.get_cell_mut(4, 6).set_type(CellType::End);
+
+        let blocks = maze.to_blocks();
+        assert_eq!((blocks.width(), blocks.height()), (15, 11));
+        assert_eq!(blocks.get_cell(1, 1).get_type(), CellType::Start);
+        assert_eq!(Maze::from_blocks(&blocks).unwrap().get_cells(), maze.get_cells());
+
+        let mut broken = blocks.clone();
+        broken.get_cell_mut(3, 3).set_type(BLOCK_WALL);
+        assert!(matches!(
+            Maze::from_blocks(&broken),
+            Err(FormatError::BlockLayout { row: 3, col: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn round_trip_keeps_wrapping_weaves_and_openings() {
+        let mut wrapped = Maze::new(9, 7);
+        wrapped.set_wrap(true, false);
+        let (_, mut maze) = MazeBuilder::generated("kruskals_weave", 4, &wrapped);
+        maze.set_opening(Opening::Entrance, 0, 2, WALL_N);
+        maze.set_opening(Opening::Exit, 6, 5, WALL_S);
+        let crossings = |maze: &Maze| {
+            (0..maze.height())
+                .flat_map(|row| (0..maze.width()).map(move |col| (row, col)))
+                .filter(|&(row, col)| maze.is_under_crossing(row, col))
+                .collect::<Vec<_>>()
+        };
+        assert!(!crossings(&maze).is_empty());
+
+        let blocks = maze.to_blocks();
+        assert!(blocks.wraps_horizontally());
+        assert_eq!(blocks.get_opening(Opening::Entrance), Some((0, 5, WALL_N)));
+        let restored = Maze::from_blocks(&blocks).unwrap();
+        assert_eq!(restored.get_cells(), maze.get_cells());
+        assert_eq!(crossings(&restored), crossings(&maze));
+        assert_eq!((restored.wraps_horizontally(), restored.wraps_vertically()), (true, false));
+        assert_eq!(restored.get_opening(Opening::Entrance), maze.get_opening(Opening::Entrance));
+        assert_eq!(restored.get_opening(Opening::Exit), maze.get_opening(Opening::Exit));
+
+        let mut stray = blocks.clone();
+        stray.set_under_crossing(2, 3, true);
+        assert!(matches!(
+            Maze::from_blocks(&stray),
+            Err(FormatError::BlockLayout { row: 2, col: 3, .. })
+        ));
+    }
+}
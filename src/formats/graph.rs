@@ -0,0 +1,207 @@
+use std::{collections::HashSet, fmt::Write};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    maze::Maze
+};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz, with each cell's position pinned to its place in the grid
+    Dot,
+    GraphMl,
+    /// One `row,col row,col weight` line per edge
+    EdgeList,
+}
+
+/// The passage graph of a maze. Edge weights count the cell-to-cell moves
+/// along the corridor an edge stands for.
+pub struct PassageGraph {
+    pub nodes: Vec<(u32, u32)>,
+    pub edges: Vec<(usize, usize, u32)>,
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Export the cells and the open passages between them as a graph.
+    /// With `contract` set, only junctions, dead ends, start and end are
+    /// kept and each corridor between them becomes one weighted edge.
+    pub fn to_graph(&self, format: GraphFormat, contract: bool) -> String {
+        let graph = self.passage_graph(contract);
+        match format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::GraphMl => graph.to_graphml(),
+            GraphFormat::EdgeList => graph.to_edge_list(),
+        }
+    }
+}
+
+impl Maze {
+    pub fn passage_graph(&self, contract: bool) -> PassageGraph {
+        let (width, height) = (self.width(), self.height());
+        let index = |(row, col): (u32, u32)| (row * width + col) as usize;
+        let cells: Vec<(u32, u32)> = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .collect();
+
+        let neighbors: Vec<Vec<(u32, u32)>> = cells
+            .iter()
+            .map(|&(row, col)| {
+                if !self.is_enabled(row, col) {
+                    return Vec::new();
+                }
+                self.passages(row, col)
+                    .into_iter()
+                    .filter(|&(nr, nc)| self.is_enabled(nr, nc))
+                    .collect()
+            })
+            .collect();
+
+        let mut node_of = vec![None; cells.len()];
+        let mut nodes = Vec::new();
+        for &cell in &cells {
+            let keep = self.is_enabled(cell.0, cell.1) && (!contract || {
+                let cell_type = self.get_cell(cell.0, cell.1).get_type();
+                neighbors[index(cell)].len() != 2 || cell_type == CellType::Start || cell_type == CellType::End
+            });
+            if keep {
+                node_of[index(cell)] = Some(nodes.len());
+                nodes.push(cell);
+            }
+        }
+
+        // Walk every corridor once, from a node until the next node
+        let mut used: HashSet<(usize, usize)> = HashSet::new();
+        let edge_key = |a: usize, b: usize| (a.min(b), a.max(b));
+        let mut edges = Vec::new();
+        let mut next_node = 0;
+        loop {
+            while next_node < nodes.len() {
+                let start = nodes[next_node];
+                for &first in &neighbors[index(start)] {
+                    if !used.insert(edge_key(index(start), index(first))) {
+                        continue;
+                    }
+
+                    let (mut previous, mut current, mut length) = (start, first, 1);
+                    while node_of[index(current)].is_none() {
+                        let step = neighbors[index(current)]
+                            .iter()
+                            .copied()
+                            .find(|&next| next != previous);
+                        match step {
+                            Some(next) if used.insert(edge_key(index(current), index(next))) => {
+                                previous = current;
+                                current = next;
+                                length += 1;
+                            }
+                            _ => break,
+                        }
+                    }
+
+                    if let Some(end) = node_of[index(current)] {
+                        edges.push((next_node, end, length));
+                    }
+                }
+                next_node += 1;
+            }
+
+            // A loop with no junction on it still needs a node to show up
+            let orphan = cells.iter().copied().find(|&cell| {
+                node_of[index(cell)].is_none()
+                    && neighbors[index(cell)].iter().any(|&next| !used.contains(&edge_key(index(cell), index(next))))
+            });
+            match orphan {
+                Some(cell) => {
+                    node_of[index(cell)] = Some(nodes.len());
+                    nodes.push(cell);
+                }
+                None => break,
+            }
+        }
+
+        PassageGraph { nodes, edges }
+    }
+}
+
+impl PassageGraph {
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph maze {\n  node [shape=point];\n");
+        for (i, &(row, col)) in self.nodes.iter().enumerate() {
+            // Graphviz puts y up, rows go down
+            writeln!(dot, "  n{} [label=\"{},{}\", pos=\"{},{}!\"];", i, row, col, col, -(row as i64)).unwrap();
+        }
+        for &(a, b, weight) in &self.edges {
+            writeln!(dot, "  n{} -- n{} [weight={}, label=\"{}\"];", a, b, weight, weight).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"row\" for=\"node\" attr.name=\"row\" attr.type=\"int\"/>\n",
+            "  <key id=\"col\" for=\"node\" attr.name=\"col\" attr.type=\"int\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n",
+            "  <graph id=\"maze\" edgedefault=\"undirected\">\n",
+        ));
+        for (i, &(row, col)) in self.nodes.iter().enumerate() {
+            writeln!(
+                xml,
+                "    <node id=\"n{}\"><data key=\"row\">{}</data><data key=\"col\">{}</data></node>",
+                i, row, col
+            ).unwrap();
+        }
+        for &(a, b, weight) in &self.edges {
+            writeln!(
+                xml,
+                "    <edge source=\"n{}\" target=\"n{}\"><data key=\"weight\">{}</data></edge>",
+                a, b, weight
+            ).unwrap();
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn to_edge_list(&self) -> String {
+        let mut list = String::new();
+        for &(a, b, weight) in &self.edges {
+            let ((r1, c1), (r2, c2)) = (self.nodes[a], self.nodes[b]);
+            writeln!(list, "{},{} {},{} {}", r1, c1, r2, c2, weight).unwrap();
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, WALL_E, WALL_N, WALL_S, WALL_W};
+
+    #[test]
+    fn contracts_corridors_into_weighted_edges() {
+        // A U through the bottom row, (0, 1) is walled in
+        let mut maze = Maze::new(3, 2);
+        let walls = [
+            [WALL_N | WALL_E | WALL_W, WALL_N | WALL_E | WALL_S | WALL_W, WALL_N | WALL_E | WALL_W],
+            [WALL_S | WALL_W, WALL_S | WALL_N, WALL_S | WALL_E],
+        ];
+        for (row, line) in walls.iter().enumerate() {
+            for (col, &wall) in line.iter().enumerate() {
+                let mut cell = Cell::new(CellType::Default);
+                cell.add_wall(wall);
+                maze.set_cell(row as u32, col as u32, cell);
+            }
+        }
+
+        let full = maze.passage_graph(false);
+        assert_eq!((full.nodes.len(), full.edges.len()), (6, 4));
+
+        assert_eq!(maze.to_graph(GraphFormat::EdgeList, true), "0,0 0,2 4\n");
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{Cell, CellType, WALL_MASK},
+    formats::{cell_count, check_walls, FormatError},
+    maze::{Maze, Opening}
+};
+
+/// Bumped whenever a field is added or changes meaning, so older readers
+/// reject a maze instead of dropping what they don't know. Version 2 adds
+/// `entrance` and `exit`.
+pub const JSON_VERSION: u32 = 2;
+
+/// On-disk layout of a maze. Cells are listed row by row; walls use the
+/// same bits as `Cell` and types use `CellType::name`.
+#[derive(Serialize, Deserialize)]
+struct MazeJson {
+    version: u32,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    wrap_horizontal: bool,
+    #[serde(default)]
+    wrap_vertical: bool,
+    walls: Vec<u8>,
+    types: Vec<String>,
+    /// `[row, col]` of every cell a weave tunnel passes under
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    crossings: Vec<[u32; 2]>,
+    /// `[row, col, wall]` of the outer walls opened as the way in and out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entrance: Option<[u32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit: Option<[u32; 3]>,
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Serialize the maze, including walls and cell types, to JSON
+    pub fn to_json(&self) -> String {
+        let mut crossings = Vec::new();
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if self.is_under_crossing(row, col) {
+                    crossings.push([row, col]);
+                }
+            }
+        }
+
+        let json = MazeJson {
+            version: JSON_VERSION,
+            width: self.width(),
+            height: self.height(),
+            wrap_horizontal: self.wraps_horizontally(),
+            wrap_vertical: self.wraps_vertically(),
+            walls: self.get_cells().iter().map(|cell| cell.raw() & WALL_MASK).collect(),
+            types: self.get_cells().iter().map(|cell| cell.get_type().name().to_string()).collect(),
+            crossings,
+            entrance: self.get_opening(Opening::Entrance).map(|(row, col, wall)| [row, col, wall as u32]),
+            exit: self.get_opening(Opening::Exit).map(|(row, col, wall)| [row, col, wall as u32]),
+        };
+
+        serde_json::to_string(&json).unwrap()
+    }
+
+    /// Load a maze saved with `to_json`. Throws a description of the first
+    /// problem found if the data is inconsistent.
+    #[wasm_bindgen(js_name = "from_json")]
+    pub fn from_json_js(json: &str) -> Result<Maze, JsValue> {
+        Maze::from_json(json).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Maze {
+    pub fn from_json(json: &str) -> Result<Maze, FormatError> {
+        let json: MazeJson = serde_json::from_str(json)
+            .map_err(|err| FormatError::Syntax(err.to_string()))?;
+
+        if json.version > JSON_VERSION {
+            return Err(FormatError::UnsupportedVersion(json.version));
+        }
+
+        let expected = cell_count(json.width, json.height)?;
+        for found in [json.walls.len(), json.types.len()] {
+            if found != expected {
+                return Err(FormatError::CellCount { expected, found });
+            }
+        }
+
+        let mut maze = Maze::new(json.width, json.height);
+        maze.set_wrap(json.wrap_horizontal, json.wrap_vertical);
+
+        for (i, (&walls, name)) in json.walls.iter().zip(&json.types).enumerate() {
+            let row = i as u32 / json.width;
+            let col = i as u32 % json.width;
+
+            if walls & !WALL_MASK != 0 {
+                return Err(FormatError::InvalidWalls { row, col, value: walls });
+            }
+            let cell_type = CellType::from_name(name)
+                .ok_or_else(|| FormatError::UnknownCellType(name.clone()))?;
+
+            let mut cell = Cell::new(cell_type);
+            cell.add_wall(walls);
+            maze.set_cell(row, col, cell);
+        }
+
+        for [row, col] in json.crossings {
+            if row >= json.height || col >= json.width {
+                return Err(FormatError::Syntax(format!("crossing ({}, {}) is outside the maze", row, col)));
+            }
+            maze.set_under_crossing(row, col, true);
+        }
+
+        for (opening, found) in [(Opening::Entrance, json.entrance), (Opening::Exit, json.exit)] {
+            if let Some([row, col, wall]) = found {
+                let inside = row < json.height && col < json.width;
+                if !inside || wall > u8::MAX as u32 || !maze.faces_outside(row, col, wall as u8) {
+                    return Err(FormatError::Syntax(format!(
+                        "{:?} at ({}, {}) is not on the outside of the maze", opening, row, col
+                    )));
+                }
+                maze.set_opening(opening, row, col, wall as u8);
+            }
+        }
+
+        check_walls(&maze)?;
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cell::{WALL_E, WALL_N, WALL_W},
+        generators::{MazeBuilder, GENERATOR_NAMES}
+    };
+
+    #[test]
+    fn round_trip_keeps_walls_and_types() {
+        let mut maze = Maze::new(3, 2);
+        let mut start = Cell::new(CellType::Start);
+        start.add_wall(WALL_E);
+        maze.set_cell(0, 0, start);
+        let mut next = Cell::new(CellType::Path);
+        next.add_wall(WALL_W);
+        maze.set_cell(0, 1, next);
+        maze.set_opening(Opening::Entrance, 0, 0, WALL_N);
+
+        let loaded = Maze::from_json(&maze.to_json()).unwrap();
+        assert_eq!(loaded.opening(Opening::Entrance), vec![0, 0, WALL_N as u32]);
+        assert_eq!(loaded.width(), 3);
+        assert_eq!(loaded.height(), 2);
+        assert_eq!(loaded.get_cells(), maze.get_cells());
+    }
+
+    #[test]
+    fn generating_closes_openings() {
+        let mut maze = Maze::new(5, 4);
+        maze.set_opening(Opening::Entrance, 0, 0, WALL_N);
+        maze.set_opening(Opening::Exit, 3, 4, WALL_E);
+
+        for name in GENERATOR_NAMES.iter() {
+            let (mut builder, generated) = MazeBuilder::generated(name, 1, &maze);
+            let mut stepped = maze.clone();
+            builder.step_to(builder.total_steps(), &mut stepped);
+
+            for generated in [generated, stepped] {
+                assert_eq!(generated.get_opening(Opening::Entrance), None, "{}", name);
+                let loaded = Maze::from_json(&generated.to_json()).unwrap();
+                assert_eq!(loaded.get_cells(), generated.get_cells(), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_data() {
+        let short = r#"{"version":1,"width":2,"height":1,"walls":[0],"types":["default","default"]}"#;
+        assert_eq!(
+            Maze::from_json(short).err(),
+            Some(FormatError::CellCount { expected: 2, found: 1 })
+        );
+
+        let half_wall = r#"{"version":1,"width":2,"height":1,"walls":[2,0],"types":["default","default"]}"#;
+        assert_eq!(
+            Maze::from_json(half_wall).err(),
+            Some(FormatError::AsymmetricWall { row: 0, col: 0, wall: WALL_E })
+        );
+
+        // Only the enabled side of a mask edge has to be walled
+        let mask_edge = r#"{"version":1,"width":2,"height":1,"walls":[2,0],"types":["default","disabled"]}"#;
+        assert!(Maze::from_json(mask_edge).is_ok());
+
+        // 65536 x 65537 wraps to 65536 cells in 32 bits
+        let huge = r#"{"version":1,"width":65536,"height":65537,"walls":[],"types":[]}"#;
+        assert!(matches!(Maze::from_json(huge), Err(FormatError::Syntax(_))));
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::{
+    cell::{WALL_E, WALL_N, WALL_S, WALL_W},
+    maze::Maze
+};
+
+mod ascii;
+mod binary;
+mod blocks;
+mod graph;
+mod tiled;
+mod trace;
+mod json;
+mod share;
+
+pub use binary::crc32;
+pub use blocks::BLOCK_WALL;
+
+/// Everything that can go wrong while loading a saved maze.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// The input isn't well-formed for the format at all
+    Syntax(String),
+    UnsupportedVersion(u32),
+    /// A per-cell list doesn't have one entry per cell
+    CellCount { expected: usize, found: usize },
+    /// A wall value uses bits outside `WALL_MASK`
+    InvalidWalls { row: u32, col: u32, value: u8 },
+    UnknownCellType(String),
+    /// A wall is set on one side of a shared edge but not on the other
+    AsymmetricWall { row: u32, col: u32, wall: u8 },
+    /// Binary data that doesn't start with the expected magic bytes
+    BadMagic,
+    /// Binary data that ends in the middle of a section
+    Truncated,
+    ChecksumMismatch { expected: u32, found: u32 },
+    /// Text input with an unexpected character, 1-based position
+    Parse { line: usize, column: usize, message: String },
+    /// A block maze with no wall maze equivalent
+    BlockLayout { row: u32, col: u32, message: String },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Syntax(message) => write!(f, "malformed maze data: {}", message),
+            FormatError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            FormatError::CellCount { expected, found } => {
+                write!(f, "expected {} cells but found {}", expected, found)
+            }
+            FormatError::InvalidWalls { row, col, value } => {
+                write!(f, "invalid wall bits {:#06b} at ({}, {})", value, row, col)
+            }
+            FormatError::UnknownCellType(name) => write!(f, "unknown cell type \"{}\"", name),
+            FormatError::AsymmetricWall { row, col, wall } => {
+                write!(f, "wall {:#06b} at ({}, {}) is not matched by its neighbour", wall, row, col)
+            }
+            FormatError::BadMagic => write!(f, "not a mazeweb binary maze"),
+            FormatError::Truncated => write!(f, "maze data ends unexpectedly"),
+            FormatError::ChecksumMismatch { expected, found } => {
+                write!(f, "checksum mismatch: stored {:08x}, computed {:08x}", expected, found)
+            }
+            FormatError::Parse { line, column, message } => {
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+            FormatError::BlockLayout { row, col, message } => {
+                write!(f, "block ({}, {}): {}", row, col, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Largest maze a loader accepts, far more than any maze that can be drawn
+/// but small enough that a damaged header can't ask for gigabytes.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// Number of cells of a `width` × `height` maze read from a file. The
+/// product is checked, a wrapped count would let a tiny body pass for a
+/// huge maze on 32-bit targets.
+pub fn cell_count(width: u32, height: u32) -> Result<usize, FormatError> {
+    match width.checked_mul(height) {
+        Some(count) if count as usize <= MAX_CELLS => Ok(count as usize),
+        _ => Err(FormatError::Syntax(format!(
+            "a {}x{} maze is larger than the limit of {} cells", width, height, MAX_CELLS
+        ))),
+    }
+}
+
+/// Check that every wall shared by two cells is stored on both of them.
+///
+/// The exceptions are a weave tunnel, where the cell being tunnelled
+/// under keeps its wall while the neighbour's side is open, and the edge
+/// of a mask, which only the enabled side has to close.
+pub fn check_walls(maze: &Maze) -> Result<(), FormatError> {
+    for row in 0..maze.height() {
+        for col in 0..maze.width() {
+            if !maze.is_enabled(row, col) {
+                continue;
+            }
+            let cell = maze.get_cell(row, col);
+            for &(wall, back, dr, dc) in &[(WALL_E, WALL_W, 0, 1), (WALL_S, WALL_N, 1, 0)] {
+                let (nr, nc) = match maze.enabled_neighbor(row, col, dr, dc) {
+                    Some(neighbor) => neighbor,
+                    None => continue,
+                };
+                let here = cell.has_wall(wall);
+                let there = maze.get_cell(nr, nc).has_wall(back);
+
+                let tunnel = (here && maze.is_under_crossing(row, col))
+                    || (there && maze.is_under_crossing(nr, nc));
+                if here != there && !tunnel {
+                    return Err(FormatError::AsymmetricWall { row, col, wall });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generators::MazeBuilder, maze::Maze};
+
+    #[test]
+    fn masked_mazes_round_trip() {
+        let mut masked = Maze::new(8, 6);
+        masked.set_mask_ascii("########\n#### ###\n########\n##  ####\n########\n########");
+
+        for name in ["aldous_broder", "kruskals", "prims"] {
+            let (_, maze) = MazeBuilder::generated(name, 3, &masked);
+            let loaded = [
+                Maze::from_json(&maze.to_json()).unwrap(),
+                Maze::from_bytes(&maze.to_bytes(true)).unwrap(),
+                Maze::from_share_code(&maze.to_share_code()).unwrap(),
+            ];
+            for loaded in loaded {
+                assert_eq!(loaded.get_cells(), maze.get_cells(), "{}", name);
+                assert!(loaded.validate().is_perfect(), "{}: {}", name, loaded.validate());
+            }
+        }
+    }
+}
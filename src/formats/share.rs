@@ -0,0 +1,205 @@
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    formats::FormatError,
+    maze::Maze
+};
+
+pub const SHARE_VERSION: u8 = 1;
+
+const FLAG_GENERATOR: u8 = 0b01;
+const FLAG_SEED: u8 = 0b10;
+
+// Nobody shares a maze whose binary form inflates past this
+const MAX_INFLATED: usize = 16 * 1024 * 1024;
+
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A decoded share code: the maze plus how it was generated, if the code
+/// says so.
+#[wasm_bindgen]
+pub struct SharedMaze {
+    maze: Maze,
+    generator: Option<String>,
+    seed: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl SharedMaze {
+    /// Decode a share code made by `Maze::to_share_code`
+    pub fn decode(code: &str) -> Result<SharedMaze, JsValue> {
+        decode_share_code(code).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    pub fn maze(&self) -> Maze {
+        self.maze.clone()
+    }
+
+    pub fn generator(&self) -> Option<String> {
+        self.generator.clone()
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+}
+
+/// Share codes are base64url without padding over:
+///
+/// ```text
+/// version u8 | flags u8 | [name length u8, name] | [seed u32 LE] | deflated `to_bytes`
+/// ```
+#[wasm_bindgen]
+impl Maze {
+    /// A short URL-safe code that reproduces this maze exactly
+    pub fn to_share_code(&self) -> String {
+        self.to_share_code_with(None, None)
+    }
+
+    /// Like `to_share_code`, but also records the generator and seed that
+    /// produced the maze. Generator names longer than 255 bytes are dropped.
+    pub fn to_share_code_with(&self, generator: Option<String>, seed: Option<u32>) -> String {
+        let generator = generator.filter(|name| name.len() <= u8::MAX as usize);
+
+        let mut flags = 0;
+        if generator.is_some() {
+            flags |= FLAG_GENERATOR;
+        }
+        if seed.is_some() {
+            flags |= FLAG_SEED;
+        }
+
+        let mut payload = vec![SHARE_VERSION, flags];
+        if let Some(name) = generator {
+            payload.push(name.len() as u8);
+            payload.extend_from_slice(name.as_bytes());
+        }
+        if let Some(seed) = seed {
+            payload.extend_from_slice(&seed.to_le_bytes());
+        }
+        payload.extend(compress_to_vec(&self.to_bytes(true), 9));
+
+        base64_url_encode(&payload)
+    }
+
+    #[wasm_bindgen(js_name = "from_share_code")]
+    pub fn from_share_code_js(code: &str) -> Result<Maze, JsValue> {
+        Maze::from_share_code(code).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+}
+
+impl Maze {
+    pub fn from_share_code(code: &str) -> Result<Maze, FormatError> {
+        decode_share_code(code).map(|shared| shared.maze)
+    }
+}
+
+pub fn decode_share_code(code: &str) -> Result<SharedMaze, FormatError> {
+    let payload = base64_url_decode(code.trim())?;
+    let mut rest = payload.as_slice();
+
+    let header = take(&mut rest, 2)?;
+    let (version, flags) = (header[0], header[1]);
+    if version > SHARE_VERSION {
+        return Err(FormatError::UnsupportedVersion(version as u32));
+    }
+
+    let generator = if flags & FLAG_GENERATOR != 0 {
+        let length = take(&mut rest, 1)?[0] as usize;
+        let name = take(&mut rest, length)?;
+        Some(String::from_utf8(name.to_vec())
+            .map_err(|_| FormatError::Syntax("generator name is not UTF-8".to_string()))?)
+    } else {
+        None
+    };
+
+    let seed = if flags & FLAG_SEED != 0 {
+        let bytes = take(&mut rest, 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    } else {
+        None
+    };
+
+    let bytes = decompress_to_vec_with_limit(rest, MAX_INFLATED)
+        .map_err(|_| FormatError::Syntax("share code is not valid compressed data".to_string()))?;
+
+    Ok(SharedMaze {
+        maze: Maze::from_bytes(&bytes)?,
+        generator,
+        seed,
+    })
+}
+
+/// Split `count` bytes off the front of `rest`
+fn take<'a>(rest: &mut &'a [u8], count: usize) -> Result<&'a [u8], FormatError> {
+    if rest.len() < count {
+        return Err(FormatError::Truncated);
+    }
+    let (head, tail) = rest.split_at(count);
+    *rest = tail;
+    Ok(head)
+}
+
+fn base64_url_encode(bytes: &[u8]) -> String {
+    let mut code = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+
+        // One character more than bytes in the chunk, no padding
+        for i in 0..=chunk.len() {
+            code.push(BASE64_URL[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    code
+}
+
+fn base64_url_decode(code: &str) -> Result<Vec<u8>, FormatError> {
+    let mut bytes = Vec::with_capacity(code.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in code.bytes() {
+        let value = BASE64_URL
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| FormatError::Syntax(format!("'{}' is not a share code character", c as char)))?;
+
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, CellType, WALL_E, WALL_W};
+
+    #[test]
+    fn round_trip_with_generator_and_seed() {
+        let mut maze = Maze::new(6, 4);
+        let mut left = Cell::new(CellType::Start);
+        left.add_wall(WALL_E);
+        maze.set_cell(2, 2, left);
+        let mut right = Cell::new(CellType::Default);
+        right.add_wall(WALL_W);
+        maze.set_cell(2, 3, right);
+
+        let code = maze.to_share_code_with(Some("kruskals".to_string()), Some(42));
+        assert!(code.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+
+        let shared = decode_share_code(&code).unwrap();
+        assert_eq!(shared.maze.get_cells(), maze.get_cells());
+        assert_eq!(shared.generator.as_deref(), Some("kruskals"));
+        assert_eq!(shared.seed, Some(42));
+        assert_eq!(Maze::from_share_code(&maze.to_share_code()).unwrap().get_cells(), maze.get_cells());
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt::Write;
+
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    formats::BLOCK_WALL,
+    maze::Maze,
+    render::edge_closed,
+    utils::escape_xml
+};
+
+const TILED_VERSION: &str = "1.10";
+
+/// Layout of the exported tile layer and the tileset it points at
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct TiledOptions {
+    tile_size: u32,
+    autotile: bool,
+    tileset_image: String,
+}
+
+#[wasm_bindgen]
+impl TiledOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TiledOptions {
+        TiledOptions {
+            tile_size: 16,
+            autotile: true,
+            tileset_image: "maze_tiles.png".to_string(),
+        }
+    }
+
+    pub fn set_tile_size(&mut self, size: u32) {
+        self.tile_size = size.max(1);
+    }
+
+    /// One tile per cell, picked by its walls: tile `n` of a 16 tile strip
+    /// has the walls whose `WALL_*` bits make up `n`. Otherwise the layer
+    /// is a block maze over a 2 tile strip, floor then wall.
+    pub fn set_autotile(&mut self, autotile: bool) {
+        self.autotile = autotile;
+    }
+
+    /// Path of the tileset image, relative to the map
+    pub fn set_tileset_image(&mut self, image: String) {
+        self.tileset_image = image;
+    }
+}
+
+impl Default for TiledOptions {
+    fn default() -> Self {
+        TiledOptions::new()
+    }
+}
+
+/// Everything both Tiled formats need
+struct TiledMap {
+    width: u32,
+    height: u32,
+    /// Global tile ids, 0 for no tile
+    data: Vec<u32>,
+    tile_count: u32,
+    /// `(name, x, y)` in pixels
+    points: Vec<(&'static str, u32, u32)>,
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Export as a Tiled TMX map with a tile layer and a `markers` object
+    /// layer holding the start and end points
+    pub fn to_tmx(&self, options: &TiledOptions) -> String {
+        let map = self.tiled_map(options);
+        let size = options.tile_size;
+        let mut tmx = String::new();
+
+        writeln!(tmx, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(
+            tmx,
+            r#"<map version="{}" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" infinite="0" nextlayerid="3" nextobjectid="{}">"#,
+            TILED_VERSION, map.width, map.height, size, size, map.points.len() + 1
+        ).unwrap();
+        writeln!(
+            tmx,
+            r#" <tileset firstgid="1" name="maze" tilewidth="{}" tileheight="{}" tilecount="{}" columns="{}">"#,
+            size, size, map.tile_count, map.tile_count
+        ).unwrap();
+        writeln!(
+            tmx,
+            r#"  <image source="{}" width="{}" height="{}"/>"#,
+            escape_xml(&options.tileset_image), size * map.tile_count, size
+        ).unwrap();
+        writeln!(tmx, " </tileset>").unwrap();
+
+        writeln!(tmx, r#" <layer id="1" name="maze" width="{}" height="{}">"#, map.width, map.height).unwrap();
+        writeln!(tmx, r#"  <data encoding="csv">"#).unwrap();
+        let rows: Vec<String> = map.data
+            .chunks(map.width.max(1) as usize)
+            .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+            .collect();
+        writeln!(tmx, "{}", rows.join(",\n")).unwrap();
+        writeln!(tmx, "  </data>").unwrap();
+        writeln!(tmx, " </layer>").unwrap();
+
+        writeln!(tmx, r#" <objectgroup id="2" name="markers">"#).unwrap();
+        for (i, &(name, x, y)) in map.points.iter().enumerate() {
+            writeln!(
+                tmx,
+                r#"  <object id="{}" name="{}" type="{}" x="{}" y="{}"><point/></object>"#,
+                i + 1, name, name, x, y
+            ).unwrap();
+        }
+        writeln!(tmx, " </objectgroup>").unwrap();
+        writeln!(tmx, "</map>").unwrap();
+        tmx
+    }
+
+    /// Export as a Tiled JSON map, with the same layers as `to_tmx`
+    pub fn to_tiled_json(&self, options: &TiledOptions) -> String {
+        let map = self.tiled_map(options);
+        let size = options.tile_size;
+        let objects: Vec<_> = map.points
+            .iter()
+            .enumerate()
+            .map(|(i, &(name, x, y))| json!({
+                "id": i + 1,
+                "name": name,
+                "type": name,
+                "point": true,
+                "x": x,
+                "y": y,
+                "width": 0,
+                "height": 0,
+                "rotation": 0,
+                "visible": true,
+            }))
+            .collect();
+
+        json!({
+            "type": "map",
+            "version": TILED_VERSION,
+            "orientation": "orthogonal",
+            "renderorder": "right-down",
+            "width": map.width,
+            "height": map.height,
+            "tilewidth": size,
+            "tileheight": size,
+            "infinite": false,
+            "nextlayerid": 3,
+            "nextobjectid": map.points.len() + 1,
+            "layers": [
+                {
+                    "id": 1,
+                    "name": "maze",
+                    "type": "tilelayer",
+                    "width": map.width,
+                    "height": map.height,
+                    "x": 0,
+                    "y": 0,
+                    "opacity": 1,
+                    "visible": true,
+                    "data": map.data,
+                },
+                {
+                    "id": 2,
+                    "name": "markers",
+                    "type": "objectgroup",
+                    "draworder": "topdown",
+                    "x": 0,
+                    "y": 0,
+                    "opacity": 1,
+                    "visible": true,
+                    "objects": objects,
+                },
+            ],
+            "tilesets": [{
+                "firstgid": 1,
+                "name": "maze",
+                "tilewidth": size,
+                "tileheight": size,
+                "tilecount": map.tile_count,
+                "columns": map.tile_count,
+                "image": options.tileset_image,
+                "imagewidth": size * map.tile_count,
+                "imageheight": size,
+                "margin": 0,
+                "spacing": 0,
+            }],
+        })
+        .to_string()
+    }
+}
+
+impl Maze {
+    fn tiled_map(&self, options: &TiledOptions) -> TiledMap {
+        let size = options.tile_size;
+        let mut points = Vec::new();
+        // Markers sit in the middle of their tile
+        let mut mark = |cell_type: CellType, row: u32, col: u32| {
+            let name = match cell_type {
+                CellType::Start => "start",
+                CellType::End => "end",
+                _ => return,
+            };
+            points.push((name, col * size + size / 2, row * size + size / 2));
+        };
+
+        if options.autotile {
+            let mut data = Vec::with_capacity(self.get_cells().len());
+            for row in 0..self.height() {
+                for col in 0..self.width() {
+                    let cell_type = self.get_cell(row, col).get_type();
+                    mark(cell_type, row, col);
+                    if cell_type == CellType::Disabled {
+                        data.push(0);
+                        continue;
+                    }
+
+                    let walls = [WALL_N, WALL_E, WALL_S, WALL_W]
+                        .iter()
+                        .filter(|&&wall| edge_closed(self, row, col, wall))
+                        .fold(0, |walls, &wall| walls | wall);
+                    data.push(1 + walls as u32);
+                }
+            }
+
+            TiledMap { width: self.width(), height: self.height(), data, tile_count: 16, points }
+        } else {
+            let blocks = self.to_blocks();
+            let data = blocks.get_cells()
+                .iter()
+                .map(|cell| match cell.get_type() {
+                    CellType::Disabled => 0,
+                    cell_type if cell_type == BLOCK_WALL => 2,
+                    _ => 1,
+                })
+                .collect();
+            for row in 0..blocks.height() {
+                for col in 0..blocks.width() {
+                    mark(blocks.get_cell(row, col).get_type(), row, col);
+                }
+            }
+
+            TiledMap { width: blocks.width(), height: blocks.height(), data, tile_count: 2, points }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn autotiles_and_marks_start_and_end() {
+        let mut maze = Maze::new(2, 1);
+        let mut start = Cell::new(CellType::Start);
+        start.add_wall(WALL_N | WALL_S | WALL_W);
+        maze.set_cell(0, 0, start);
+        let mut end = Cell::new(CellType::End);
+        end.add_wall(WALL_N | WALL_E | WALL_S);
+        maze.set_cell(0, 1, end);
+
+        let options = TiledOptions::new();
+        let map: serde_json::Value = serde_json::from_str(&maze.to_tiled_json(&options)).unwrap();
+        let data = &map["layers"][0]["data"];
+        assert_eq!(data[0], 1 + (WALL_N | WALL_S | WALL_W) as u32);
+        assert_eq!(data[1], 1 + (WALL_N | WALL_E | WALL_S) as u32);
+        let end_point = &map["layers"][1]["objects"][1];
+        assert_eq!((end_point["name"].as_str(), end_point["x"].as_u64()), (Some("end"), Some(24)));
+
+        let mut blocks = TiledOptions::new();
+        blocks.set_autotile(false);
+        let tmx = maze.to_tmx(&blocks);
+        assert!(tmx.contains(r#"width="5" height="3""#), "{}", tmx);
+        assert!(tmx.contains(r#"name="start" type="start" x="24" y="24""#), "{}", tmx);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{Cell, CellType},
+    formats::FormatError,
+    generators::MazeBuilder,
+    maze::{Maze, MazeChange}
+};
+
+/// Bumped whenever a field changes meaning. Readers reject newer versions.
+pub const TRACE_VERSION: u32 = 1;
+
+/// The only kind of run recorded so far
+const GENERATOR_KIND: &str = "generator";
+
+/// A recorded run: the maze it started from, what produced it and every
+/// step. Changes are `[row, col, old, new]` with raw `Cell` values, and
+/// the maze uses the `to_json` layout.
+#[derive(Serialize, Deserialize)]
+struct TraceJson {
+    version: u32,
+    /// What made the steps, `"generator"` for a `MazeBuilder` run
+    kind: String,
+    algorithm: String,
+    #[serde(default)]
+    parameters: BTreeMap<String, f64>,
+    #[serde(default)]
+    seed: Option<u32>,
+    maze: serde_json::Value,
+    steps: Vec<Vec<[u32; 4]>>,
+    /// `[step, row, col]` of every weave crossing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    crossings: Vec<[u32; 3]>,
+}
+
+#[wasm_bindgen]
+impl MazeBuilder {
+    /// Record the steps of the last `generate_all` as a trace. `maze` is
+    /// the maze that was passed to it.
+    pub fn to_trace(&self, maze: &Maze) -> String {
+        let trace = TraceJson {
+            version: TRACE_VERSION,
+            kind: GENERATOR_KIND.to_string(),
+            algorithm: self.generator_name(),
+            parameters: self.parameters().into_iter().collect(),
+            seed: self.seed(),
+            maze: serde_json::from_str(&maze.to_json()).unwrap(),
+            steps: self
+                .steps()
+                .iter()
+                .map(|step| {
+                    step.iter()
+                        .map(|change| [change.row, change.col, change.old.raw() as u32, change.new.raw() as u32])
+                        .collect()
+                })
+                .collect(),
+            crossings: self
+                .crossings()
+                .iter()
+                .map(|&(step, row, col)| [step as u32, row, col])
+                .collect(),
+        };
+
+        serde_json::to_string(&trace).unwrap()
+    }
+
+    /// Replace this builder with a recorded run and return the maze it
+    /// starts from, ready for `step_forward`. Throws a description of the
+    /// first problem found if the trace doesn't replay cleanly.
+    pub fn load_trace(&mut self, trace: &str) -> Result<Maze, JsValue> {
+        let (builder, maze) = MazeBuilder::from_trace(trace)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        *self = builder;
+        Ok(maze)
+    }
+}
+
+impl MazeBuilder {
+    pub fn from_trace(trace: &str) -> Result<(MazeBuilder, Maze), FormatError> {
+        let trace: TraceJson = serde_json::from_str(trace)
+            .map_err(|err| FormatError::Syntax(err.to_string()))?;
+
+        if trace.version > TRACE_VERSION {
+            return Err(FormatError::UnsupportedVersion(trace.version));
+        }
+        if trace.kind != GENERATOR_KIND {
+            return Err(FormatError::Syntax(format!("unknown trace kind \"{}\"", trace.kind)));
+        }
+        let maze = Maze::from_json(&trace.maze.to_string())?;
+
+        // Replay the steps once, so a trace that doesn't fit its maze is
+        // caught here rather than halfway through playback
+        let mut replay = maze.clone();
+        let mut steps = Vec::with_capacity(trace.steps.len());
+        for (i, step) in trace.steps.iter().enumerate() {
+            let mut changes = Vec::with_capacity(step.len());
+            for &[row, col, old, new] in step {
+                if row >= maze.height() || col >= maze.width() {
+                    return Err(FormatError::Syntax(format!("step {} changes ({}, {}) outside the maze", i, row, col)));
+                }
+                if old > u8::MAX as u32 || new > u8::MAX as u32 {
+                    return Err(FormatError::Syntax(format!("step {} has a cell value above 255", i)));
+                }
+
+                let (mut old_cell, mut new_cell) = (Cell::new(CellType::Default), Cell::new(CellType::Default));
+                old_cell.set_raw(old as u8);
+                new_cell.set_raw(new as u8);
+                if replay.get_cell(row, col) != old_cell {
+                    return Err(FormatError::Syntax(format!(
+                        "step {} expects ({}, {}) to be {:#010b} but it is {:#010b}",
+                        i, row, col, old, replay.get_cell(row, col).raw()
+                    )));
+                }
+
+                replay.set_cell(row, col, new_cell);
+                changes.push(MazeChange { row, col, old: old_cell, new: new_cell });
+            }
+            steps.push(changes);
+        }
+
+        let mut crossings = Vec::with_capacity(trace.crossings.len());
+        for [step, row, col] in trace.crossings {
+            if step as usize >= steps.len() || row >= maze.height() || col >= maze.width() {
+                return Err(FormatError::Syntax(format!("crossing ({}, {}) at step {} is out of range", row, col, step)));
+            }
+            crossings.push((step as usize, row, col));
+        }
+
+        let parameters: Vec<(String, f64)> = trace.parameters.into_iter().collect();
+        let builder = MazeBuilder::from_recording(&trace.algorithm, &parameters, trace.seed, steps, crossings);
+        Ok((builder, maze))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_a_recorded_weave() {
+        let mut maze = Maze::new(6, 6);
+        let (builder, expected) = MazeBuilder::generated("kruskals_weave", 9, &maze);
+
+        let trace = builder.to_trace(&maze);
+        let (mut loaded, start) = MazeBuilder::from_trace(&trace).unwrap();
+        assert_eq!(loaded.generator_name(), "kruskals_weave");
+        assert_eq!(loaded.parameters(), builder.parameters());
+        assert_eq!(loaded.total_steps(), builder.total_steps());
+
+        maze = start;
+        while loaded.step_forward(&mut maze) {}
+        assert_eq!(maze.get_cells(), expected.get_cells());
+        assert_eq!(maze.to_json(), expected.to_json());
+
+        // Moves the first change's row off the maze
+        let tampered = trace.replacen("\"steps\":[[[", "\"steps\":[[[9", 1);
+        assert!(matches!(MazeBuilder::from_trace(&tampered), Err(FormatError::Syntax(message)) if message.contains("outside")));
+
+        let solver = trace.replacen("\"kind\":\"generator\"", "\"kind\":\"solver\"", 1);
+        assert!(matches!(MazeBuilder::from_trace(&solver), Err(FormatError::Syntax(message)) if message.contains("kind")));
+    }
+}
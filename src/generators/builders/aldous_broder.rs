@@ -1,6 +1,9 @@
+use crate::utils::random;
+
 use crate::{
     cell::CellType,
-    generators::generator::MazeGenerator,
+    generators::{generator::MazeGenerator, grid_generator::GridGenerator},
+    grid::{GridChange, GridMaze},
     maze::{
         Maze,
         MazeChange
@@ -18,58 +21,136 @@ impl MazeGenerator for AdlousBroder {
         let mut steps = Vec::new();
         let mut maze = original_maze.clone();
 
-        // Initialize total cells and visited cells
-        self.total_cells = maze.get_cells().len();
-        self.visited_cells = 0;
-
         // Set all cells to be walls
         let walling_step: Vec<MazeChange> = self.add_all_walls(&mut maze);
         if !walling_step.is_empty() {
             steps.push(walling_step);
         }
 
-        let (mut row, mut col) = utils::choose_random_cell(maze.height(), maze.width());
+        // The walk can't leave the masked region it starts in, so each region
+        // gets a walk of its own
+        let mut start = maze.random_enabled_cell();
+        if start.is_none() {
+            return Some(steps);
+        }
+        while let Some((mut row, mut col)) = start {
+            self.total_cells = maze.region_size(row, col);
+            self.visited_cells = 0;
+
+            let mut first_step: Vec<MazeChange> = Vec::new();
+            let mut first = maze.get_cell(row, col);
+            first.set_type(CellType::Current);
+            self.mark_cell(&mut maze, row, col, first, &mut first_step);
+            steps.push(first_step);
+            self.visited_cells += 1;
+
+            while self.total_cells > self.visited_cells {
+                let (next_row, next_col) = loop {
+                    let next = utils::pick_random_neighbor(
+                        row,
+                        col,
+                        maze.width(),
+                        maze.height(),
+                        maze.wraps_horizontally(),
+                        maze.wraps_vertically(),
+                    );
+                    if maze.is_enabled(next.0, next.1) {
+                        break next;
+                    }
+                };
+
+                let mut step = Vec::new();
+
+                // mark old as visited
+                let mut old = maze.get_cell(row, col);
+                old.set_type(CellType::Visited);
+                self.mark_cell(&mut maze, row, col, old, &mut step);
+
+                // If it's not yet visited, carve the path and mark as visited
+                let neighbor = maze.get_cell(next_row, next_col);
+                if neighbor.get_type() != CellType::Visited {
+                    self.remove_wall_between(&mut maze, row, col, next_row, next_col, &mut step);
+                    self.visited_cells += 1;
+                }
+
+                // Mark the next cell as current
+                let mut next = maze.get_cell(next_row, next_col);
+                next.set_type(CellType::Current);
+                self.mark_cell(&mut maze, next_row, next_col, next, &mut step);
+
+                // push the step to the steps vector
+                steps.push(step);
+
+
+                // Move to the next cell
+                row = next_row;
+                col = next_col;
+            }
+
+            start = self.random_unvisited_cell(&maze);
+            if start.is_some() {
+                // Leave the finished region before walking the next one
+                let mut step = Vec::new();
+                let mut last = maze.get_cell(row, col);
+                last.set_type(CellType::Visited);
+                self.mark_cell(&mut maze, row, col, last, &mut step);
+                steps.push(step);
+            }
+        }
+
+        // Remove all visited cells and set them to default
+        let step = self.set_all_visited_to_default(&mut maze);
+        steps.push(step);
+
+        Some(steps)
+    }
+}
 
-        let mut first_step: Vec<MazeChange> = Vec::new();
-        let mut first = maze.get_cell(row, col);
-        first.set_type(CellType::Current);
-        self.mark_cell(&mut maze, row, col, first, &mut first_step);
+impl GridGenerator for AdlousBroder {
+    fn generate_grid_steps(&mut self, grid: &dyn GridMaze) -> Option<Vec<Vec<GridChange>>> {
+        let mut steps = Vec::new();
+        let mut cells = grid.grid_cells().to_vec();
+
+        self.total_cells = cells.len();
+        self.visited_cells = 0;
+        if self.total_cells == 0 {
+            return Some(steps);
+        }
+
+        let walling_step = self.add_all_grid_walls(grid, &mut cells);
+        if !walling_step.is_empty() {
+            steps.push(walling_step);
+        }
+
+        let mut current = (random() * self.total_cells as f64).floor() as usize;
+
+        let mut first_step = Vec::new();
+        self.mark_grid_cell(&mut cells, current, CellType::Current, &mut first_step);
         steps.push(first_step);
         self.visited_cells += 1;
 
         while self.total_cells > self.visited_cells {
-            let (next_row, next_col) = utils::pick_random_neighbor(row, col, maze.width(), maze.height());
+            let links = grid.links(current);
+            let link = links[(random() * links.len() as f64).floor() as usize];
 
             let mut step = Vec::new();
 
             // mark old as visited
-            let mut old = maze.get_cell(row, col);
-            old.set_type(CellType::Visited);
-            self.mark_cell(&mut maze, row, col, old, &mut step);
-
-            // If it's not yet visited, carve the path and mark as visited
-            let neighbor = maze.get_cell(next_row, next_col);
-            if neighbor.get_type() != CellType::Visited {
-                self.remove_wall_between(&mut maze, row, col, next_row, next_col, &mut step);
+            self.mark_grid_cell(&mut cells, current, CellType::Visited, &mut step);
+
+            // If it's not yet visited, carve the path
+            if cells[link.neighbor].get_type() != CellType::Visited {
+                self.remove_grid_wall(&mut cells, current, link, &mut step);
                 self.visited_cells += 1;
             }
 
-            // Mark the next cell as current
-            let mut next = maze.get_cell(next_row, next_col);
-            next.set_type(CellType::Current);
-            self.mark_cell(&mut maze, next_row, next_col, next, &mut step);
-
-            // push the step to the steps vector
+            self.mark_grid_cell(&mut cells, link.neighbor, CellType::Current, &mut step);
             steps.push(step);
 
-
-            // Move to the next cell
-            row = next_row;
-            col = next_col;
+            current = link.neighbor;
         }
 
-        // Remove all visited cells and set them to default
-        let step = self.set_all_visited_to_default(&mut maze);
+        let step = self.set_all_grid_visited_to_default(&mut cells);
         steps.push(step);
 
         Some(steps)
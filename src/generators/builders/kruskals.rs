@@ -1,6 +1,11 @@
-use js_sys::Math::random;
+use crate::utils::random;
 
-use crate::{generators::generator::MazeGenerator, maze::{Maze, MazeChange}, utils};
+use crate::{
+    generators::{generator::MazeGenerator, grid_generator::GridGenerator},
+    grid::{GridChange, GridMaze},
+    maze::{Maze, MazeChange},
+    utils
+};
 
 
 // A simple Union-Find data structure for Kruskal's algorithm with path compression and union by size.
@@ -10,14 +15,14 @@ pub struct UnionFind {
 }
 
 impl UnionFind {
-    fn new(n: usize) -> Self {
+    pub fn new(n: usize) -> Self {
         UnionFind {
             parent: (0..n).collect(),
             size: vec![1; n],
         }
     }
 
-    fn find(&mut self, mut x: usize) -> usize {
+    pub fn find(&mut self, mut x: usize) -> usize {
         // find with path-compression
         let mut root = x;
         while self.parent[root] != root {
@@ -32,7 +37,7 @@ impl UnionFind {
         root
     }
 
-    fn union(&mut self, a: usize, b: usize) -> bool {
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
         let root_a = self.find(a);
         let root_b = self.find(b);
         if root_a == root_b {
@@ -52,7 +57,13 @@ impl UnionFind {
 
 
 
-pub struct Kruskals;
+/// Share of the cells a weave maze tries to turn into crossings
+pub const WEAVE_DENSITY: f64 = 0.3;
+
+pub struct Kruskals {
+    weave_density: f64,
+    crossings: Vec<(usize, u32, u32)>,
+}
 
 impl MazeGenerator for Kruskals {
     fn generate_maze_steps(&mut self, original_maze: &Maze) -> Option<Vec<Vec<MazeChange>>> {
@@ -60,7 +71,9 @@ impl MazeGenerator for Kruskals {
         let mut maze = original_maze.clone();
         let h = maze.height() as usize;
         let w = maze.width() as usize;
-        let total_cells = h * w;
+        let total_cells = maze.enabled_cells();
+        maze.clear_under_crossings();
+        self.crossings.clear();
 
         // Make all into walls
         let walling_step: Vec<MazeChange> = self.add_all_walls(&mut maze);
@@ -68,34 +81,61 @@ impl MazeGenerator for Kruskals {
             steps.push(walling_step);
         }
 
-        // Collect all walls
+        let mut uf = UnionFind::new(h * w);
+        let mut carved = 0;
+
+        // Pre-seed the weave crossings before the regular passes, so the
+        // union-find already knows what each tunnel connects
+        if self.weave_density > 0.0 {
+            let mut candidates = Vec::new();
+            for r in 0..maze.height() {
+                for c in 0..maze.width() {
+                    candidates.push((r, c));
+                }
+            }
+            utils::shuffle(&mut candidates);
+
+            let tries = (candidates.len() as f64 * self.weave_density) as usize;
+            for &(r, c) in candidates.iter().take(tries) {
+                let mut step = Vec::new();
+                if self.add_crossing(&mut maze, &mut uf, r, c, &mut step) {
+                    self.crossings.push((steps.len(), r, c));
+                    steps.push(step);
+                    carved += 3;
+                }
+            }
+        }
+
+        // Collect all walls between enabled cells, including the ones across
+        // a wrapped seam
         let mut walls = Vec::new();
         for r in 0..maze.height() {
             for c in 0..maze.width() {
-                if r + 1 < maze.height() {
-                    walls.push((r, c, 1, 0));
+                // Crossings already have all the passages they can take
+                if !maze.is_enabled(r, c) || maze.is_under_crossing(r, c) {
+                    continue;
                 }
-                if c + 1 < maze.width() {
-                    walls.push((r, c, 0, 1));
+                for &(dr, dc) in &[(1, 0), (0, 1)] {
+                    if let Some((nr, nc)) = maze.neighbor(r, c, dr, dc) {
+                        if maze.is_enabled(nr, nc) && !maze.is_under_crossing(nr, nc) {
+                            walls.push((r, c, dr, dc));
+                        }
+                    }
                 }
             }
         }
 
         // Shuffle the walls to randomize the order
-        for i in (1..walls.len()).rev() {
-            // pick a random index j in [0..=i]
-            let j = (random() * ((i + 1) as f64)).floor() as usize;
-            walls.swap(i, j);
-        }
+        utils::shuffle(&mut walls);
 
-        let mut uf = UnionFind::new(total_cells);
-        let mut carved = 0;
         for &(r, c, dr, dc) in &walls {
-            if carved == total_cells - 1 {
+            // A spanning tree of the enabled cells has one passage less than
+            // it has cells. If the mask splits the maze this is never reached
+            // and every region gets its own tree instead.
+            if carved + 1 >= total_cells {
                 break;
             }
-            let nr = (r as i32 + dr as i32) as u32;
-            let nc = (c as i32 + dc as i32) as u32;
+            let (nr, nc) = maze.neighbor(r, c, dr, dc).unwrap();
 
             let idx1 = (r as usize) * w + (c as usize);
             let idx2 = (nr as usize) * w + (nc as usize);
@@ -111,10 +151,174 @@ impl MazeGenerator for Kruskals {
 
         Some(steps)
     }
+
+    fn crossings(&self) -> Vec<(usize, u32, u32)> {
+        self.crossings.clone()
+    }
+
+    fn parameters(&self) -> Vec<(String, f64)> {
+        vec![("weave_density".to_string(), self.weave_density)]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f64) {
+        if name == "weave_density" {
+            self.weave_density = value;
+        }
+    }
+}
+
+impl GridGenerator for Kruskals {
+    fn generate_grid_steps(&mut self, grid: &dyn GridMaze) -> Option<Vec<Vec<GridChange>>> {
+        let mut steps = Vec::new();
+        let mut cells = grid.grid_cells().to_vec();
+        let total_cells = grid.cell_count();
+
+        let walling_step = self.add_all_grid_walls(grid, &mut cells);
+        if !walling_step.is_empty() {
+            steps.push(walling_step);
+        }
+
+        // Collect every shared wall once, from the lower index side
+        let mut walls = Vec::new();
+        for index in 0..total_cells {
+            for link in grid.links(index) {
+                if link.neighbor > index {
+                    walls.push((index, link));
+                }
+            }
+        }
+
+        utils::shuffle(&mut walls);
+
+        let mut uf = UnionFind::new(total_cells);
+        let mut carved = 0;
+        for &(index, link) in &walls {
+            if carved + 1 >= total_cells {
+                break;
+            }
+
+            if uf.union(index, link.neighbor) {
+                let mut step = Vec::new();
+                self.remove_grid_wall(&mut cells, index, link, &mut step);
+                steps.push(step);
+                carved += 1;
+            }
+        }
+
+        Some(steps)
+    }
 }
 
 impl Kruskals {
     pub fn new() -> Self {
-        Kruskals
+        Kruskals {
+            weave_density: 0.0,
+            crossings: Vec::new(),
+        }
+    }
+
+    /// A Kruskal's generator for weave mazes, where about `density` of the
+    /// cells are tried as places for one passage to tunnel under another.
+    pub fn weave(density: f64) -> Self {
+        Kruskals {
+            weave_density: density,
+            crossings: Vec::new(),
+        }
+    }
+
+    /// Turn a cell into a crossing if it and its four neighbours are still in
+    /// five separate trees: carve the passage over the cell and open the
+    /// perpendicular one underneath it.
+    fn add_crossing(
+        &self,
+        maze: &mut Maze,
+        uf: &mut UnionFind,
+        row: u32,
+        col: u32,
+        step: &mut Vec<MazeChange>,
+    ) -> bool {
+        let w = maze.width() as usize;
+        let index = |(r, c): (u32, u32)| (r as usize) * w + (c as usize);
+
+        let mut neighbors = Vec::new();
+        for &(dr, dc) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            match maze.neighbor(row, col, dr, dc) {
+                Some((nr, nc)) if maze.is_enabled(nr, nc) && !maze.is_under_crossing(nr, nc) => {
+                    neighbors.push((nr, nc));
+                }
+                _ => return false,
+            }
+        }
+        if !maze.is_enabled(row, col) || maze.is_under_crossing(row, col) {
+            return false;
+        }
+
+        let mut roots = vec![uf.find(index((row, col)))];
+        for &neighbor in &neighbors {
+            let root = uf.find(index(neighbor));
+            if roots.contains(&root) {
+                return false;
+            }
+            roots.push(root);
+        }
+
+        // neighbors is [north, south, west, east]
+        let (over, under) = if random() < 0.5 {
+            ([neighbors[0], neighbors[1]], [neighbors[2], neighbors[3]])
+        } else {
+            ([neighbors[2], neighbors[3]], [neighbors[0], neighbors[1]])
+        };
+
+        for &(r, c) in &over {
+            self.remove_wall_between(maze, row, col, r, c, step);
+            uf.union(index((row, col)), index((r, c)));
+        }
+        for &(r, c) in &under {
+            self.remove_wall_toward(maze, r, c, row, col, step);
+        }
+        uf.union(index(under[0]), index(under[1]));
+        maze.set_under_crossing(row, col, true);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{WALL_E, WALL_N};
+
+    #[test]
+    fn crossings_connect_both_axes() {
+        let mut axes = Vec::new();
+        for seed in 1..10 {
+            utils::seed_random(Some(seed));
+            let kruskals = Kruskals::weave(1.0);
+            let mut maze = Maze::new(3, 3);
+            let mut step = Vec::new();
+            kruskals.add_all_walls(&mut maze);
+            assert!(kruskals.add_crossing(&mut maze, &mut UnionFind::new(9), 1, 1, &mut step));
+
+            // The passage on top goes through the crossing, the one below
+            // links the two cells on either side of it
+            let (north, south, west, east) = ((0, 1), (2, 1), (1, 0), (1, 2));
+            let over_vertical = !maze.get_cell(1, 1).has_wall(WALL_N);
+            let ((a, b), (c, d)) = if over_vertical {
+                ((north, south), (west, east))
+            } else {
+                ((west, east), (north, south))
+            };
+            let mut through = maze.passages(1, 1);
+            through.sort_unstable();
+            let mut expected = vec![a, b];
+            expected.sort_unstable();
+            assert_eq!(through, expected);
+            assert_eq!(maze.passages(c.0, c.1), vec![d]);
+            assert_eq!(maze.passages(d.0, d.1), vec![c]);
+            assert!(maze.get_cell(1, 1).has_wall(if over_vertical { WALL_E } else { WALL_N }));
+            axes.push(over_vertical);
+        }
+        utils::seed_random(None);
+        assert!(axes.contains(&true) && axes.contains(&false));
     }
 }
@@ -5,5 +5,5 @@ mod prims;
 
 pub use aldous_broder::AdlousBroder;
 pub use recursive_division::RecursiveDivision;
-pub use kruskals::Kruskals;
+pub use kruskals::{Kruskals, UnionFind, WEAVE_DENSITY};
 pub use prims::Prims;
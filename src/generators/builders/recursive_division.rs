@@ -1,6 +1,9 @@
-use js_sys::Math::random;
+use std::collections::HashMap;
+
+use crate::utils::random;
 use crate::{
-    generators::generator::MazeGenerator,
+    cell::{WALL_E, WALL_N, WALL_S, WALL_W},
+    generators::{builders::UnionFind, generator::MazeGenerator},
     maze::{Maze, MazeChange}
 };
 
@@ -17,6 +20,58 @@ impl MazeGenerator for RecursiveDivision {
             steps.push(clear_step);
         }
 
+        // Close the outer boundary again, and the seams of a wrapped maze,
+        // or every seam passage would stay open
+        let mut boundary_step = Vec::new();
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                let old = maze.get_cell(row, col);
+                let mut new = old;
+                for &(wall, dr, dc) in &[(WALL_N, -1, 0), (WALL_E, 0, 1), (WALL_S, 1, 0), (WALL_W, 0, -1)] {
+                    if maze.neighbor(row, col, dr, dc).is_none() {
+                        new.add_wall(wall);
+                    }
+                }
+                if new != old {
+                    maze.set_cell(row, col, new);
+                    boundary_step.push(MazeChange { row, col, old, new });
+                }
+            }
+        }
+        if maze.wraps_horizontally() {
+            for row in 0..maze.height() {
+                self.add_wall_dir(&mut maze, row, 0, 0, -1, &mut boundary_step);
+            }
+        }
+        if maze.wraps_vertically() {
+            for col in 0..maze.width() {
+                self.add_wall_dir(&mut maze, 0, col, -1, 0, &mut boundary_step);
+            }
+        }
+        if !boundary_step.is_empty() {
+            steps.push(boundary_step);
+        }
+
+        // Fence off cells outside the mask before dividing what's inside
+        let mut mask_step = Vec::new();
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                if maze.is_enabled(row, col) {
+                    continue;
+                }
+                for &(dr, dc) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+                        if maze.is_enabled(nr, nc) {
+                            self.add_wall_dir(&mut maze, nr, nc, -dr, -dc, &mut mask_step);
+                        }
+                    }
+                }
+            }
+        }
+        if !mask_step.is_empty() {
+            steps.push(mask_step);
+        }
+
         let mut stack = vec![Division {
             x: 0,
             y: 0,
@@ -36,17 +91,20 @@ impl MazeGenerator for RecursiveDivision {
             let wx = if horizontal { x } else { x + 1 + (random() * ((width - 2) as f64)).floor() as u32 };
             let wy = if horizontal { y + 1 + (random() * ((height - 2) as f64)).floor() as u32 } else { y };
 
-            // Choose passage position on the wall line
-            let px = if horizontal { x + (random() * (width as f64)).floor() as u32 } else { wx };
-            let py = if horizontal { wy } else { y + (random() * (height as f64)).floor() as u32 };
-
             let dx = if horizontal { 1 } else { 0 };
             let dy = if horizontal { 0 } else { 1 };
             let length = if horizontal { width } else { height };
 
+            // Choose where along the wall line to look for a passage first
+            let offset = (random() * (length as f64)).floor() as u32;
+
+            // The cell on the far side of the wall from (cy, cx)
+            let across = |cy: u32, cx: u32| if horizontal { (cy - 1, cx) } else { (cy, cx - 1) };
+
             let mut wall_step = Vec::new();
 
-            // Place wall
+            // Place wall, leaving out the parts along the mask that are
+            // fenced off already
             for i in 0..length {
                 let cx = wx + i * dx;
                 let cy = wy + i * dy;
@@ -54,6 +112,10 @@ impl MazeGenerator for RecursiveDivision {
                 if cx >= maze.width() || cy >= maze.height() {
                     continue;
                 }
+                let (oy, ox) = across(cy, cx);
+                if !maze.is_enabled(cy, cx) || !maze.is_enabled(oy, ox) {
+                    continue;
+                }
 
                 if horizontal {
                     self.add_wall_dir(&mut maze, cy, cx, -1, 0, &mut wall_step);
@@ -66,31 +128,49 @@ impl MazeGenerator for RecursiveDivision {
                 steps.push(wall_step);
             }
 
+            // The areas on either side of the wall
+            let (first, second) = if horizontal {
+                ((x, y, width, wy - y), (x, wy, width, y + height - wy))
+            } else {
+                ((x, y, wx - x, height), (wx, y, x + width - wx, height))
+            };
+
+            // A mask can cut each side into several pieces. Open one passage
+            // for every pair of pieces the wall would otherwise keep apart, so
+            // everything that touched before stays joined without loops. An
+            // unmasked area has one piece per side and gets a single passage.
+            let mut pieces = HashMap::new();
+            let mut count = 0;
+            for area in [first, second] {
+                count = label_pieces(&maze, area, count, &mut pieces);
+            }
+            let mut joined = UnionFind::new(count);
+
             // Explicitly visualize removing walls for the passage
             let mut carve = Vec::new();
-            if horizontal {
-                self.remove_wall_dir(&mut maze, py, px, -1, 0, &mut carve);
-            } else {
-                self.remove_wall_dir(&mut maze, py, px, 0, -1, &mut carve);
+            for k in 0..length {
+                let i = (offset + k) % length;
+                let (cx, cy) = (wx + i * dx, wy + i * dy);
+                let (here, there) = match (pieces.get(&(cy, cx)), pieces.get(&across(cy, cx))) {
+                    (Some(&here), Some(&there)) => (here, there),
+                    _ => continue,
+                };
+                if !joined.union(here, there) {
+                    continue;
+                }
+
+                if horizontal {
+                    self.remove_wall_dir(&mut maze, cy, cx, -1, 0, &mut carve);
+                } else {
+                    self.remove_wall_dir(&mut maze, cy, cx, 0, -1, &mut carve);
+                }
             }
             if !carve.is_empty() {
                 steps.push(carve);
             }
 
             // Subdivide the remaining areas
-            let (nx1, ny1, w1, h1, nx2, ny2, w2, h2) = if horizontal {
-                // top region
-                (x, y, width, wy - y,
-                 // bottom region
-                 x, wy, width, y + height - wy)
-            } else {
-                // left region
-                (x, y, wx - x, height,
-                 // right region
-                 wx, y, x + width - wx, height)
-            };
-
-            for (sx, sy, sw, sh) in [(nx1, ny1, w1, h1), (nx2, ny2, w2, h2)] {
+            for (sx, sy, sw, sh) in [first, second] {
                 if sw >= 2 && sh >= 2 {
                     stack.push(Division {
                         x: sx,
@@ -122,6 +202,36 @@ pub enum Orientation {
     Horizontal,
 }
 
+/// Number the pieces of enabled cells in an `(x, y, width, height)` area,
+/// starting from `next`, as `(row, col) -> piece`. Cells join a piece by
+/// stepping between enabled neighbours inside the area. Returns the next
+/// free number.
+fn label_pieces(maze: &Maze, (x, y, width, height): (u32, u32, u32, u32), mut next: usize, pieces: &mut HashMap<(u32, u32), usize>) -> usize {
+    for row in y..y + height {
+        for col in x..x + width {
+            if !maze.is_enabled(row, col) || pieces.contains_key(&(row, col)) {
+                continue;
+            }
+
+            pieces.insert((row, col), next);
+            let mut stack = vec![(row, col)];
+            while let Some((r, c)) = stack.pop() {
+                let around = [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)];
+                for (nr, nc) in around {
+                    let inside = (y..y + height).contains(&nr) && (x..x + width).contains(&nc);
+                    if inside && maze.is_enabled(nr, nc) && !pieces.contains_key(&(nr, nc)) {
+                        pieces.insert((nr, nc), next);
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+            next += 1;
+        }
+    }
+
+    next
+}
+
 impl RecursiveDivision {
     pub fn new() -> Self {
         RecursiveDivision
@@ -141,3 +251,38 @@ impl RecursiveDivision {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::MazeBuilder;
+
+    #[test]
+    fn keeps_the_outer_boundary_closed() {
+        let (_, maze) = MazeBuilder::generated("recursive_division", 2, &Maze::new(6, 5));
+
+        for row in 0..5 {
+            assert!(maze.get_cell(row, 0).has_wall(WALL_W));
+            assert!(maze.get_cell(row, 5).has_wall(WALL_E));
+        }
+        for col in 0..6 {
+            assert!(maze.get_cell(0, col).has_wall(WALL_N));
+            assert!(maze.get_cell(4, col).has_wall(WALL_S));
+        }
+    }
+
+    #[test]
+    fn carves_every_piece_of_a_mask() {
+        // Two regions with holes that division walls cut across
+        let mut masked = Maze::new(1, 1);
+        masked.set_mask_ascii("####.####\n#..#.#.##\n##.#.#.##\n####.####");
+
+        for seed in 1..=10 {
+            let (_, maze) = MazeBuilder::generated("recursive_division", seed, &masked);
+            let report = maze.validate();
+            assert!(report.is_consistent(), "seed {}: {}", seed, report);
+            assert_eq!(report.cycles(), 0, "seed {}", seed);
+            assert_eq!(maze.metrics().components(), 2, "seed {}", seed);
+        }
+    }
+}
@@ -12,15 +12,32 @@ use crate::{
         AdlousBroder,
         Kruskals,
         Prims,
-        RecursiveDivision
+        RecursiveDivision,
+        WEAVE_DENSITY
     },
-    maze::{Maze, MazeChange}
+    maze::{Maze, MazeChange},
+    utils
 };
 
 
 pub trait MazeGenerator {
     fn generate_maze_steps(&mut self, maze: &Maze) -> Option<Vec<Vec<MazeChange>>>;
 
+    /// Cells the last generated maze tunnels under, as `(step, row, col)`
+    /// where `step` is the step that creates the crossing. Only weave
+    /// generators have any.
+    fn crossings(&self) -> Vec<(usize, u32, u32)> {
+        Vec::new()
+    }
+
+    /// Tunable settings as `(name, value)`, recorded in traces
+    fn parameters(&self) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+
+    /// Change a setting named by `parameters`. Unknown names are ignored.
+    fn set_parameter(&mut self, _name: &str, _value: f64) {}
+
     /// Default method: mark a cell with any new state and record the change
     fn mark_cell(
         &self,
@@ -61,11 +78,12 @@ pub trait MazeGenerator {
         let old_c1 = maze.get_cell(row1, col1);
         let old_c2 = maze.get_cell(row2, col2);
 
-        let (wall_from_c1, wall_from_c2) = match (row2 as i32 - row1 as i32, col2 as i32 - col1 as i32) {
-            (-1, 0) => (WALL_N, WALL_S),
-            (1, 0) => (WALL_S, WALL_N),
-            (0, -1) => (WALL_W, WALL_E),
-            (0, 1) => (WALL_E, WALL_W),
+        // Cells across a seam are adjacent too, so ask the maze for the direction
+        let (wall_from_c1, wall_from_c2) = match maze.direction(row1, col1, row2, col2) {
+            Some((-1, 0)) => (WALL_N, WALL_S),
+            Some((1, 0)) => (WALL_S, WALL_N),
+            Some((0, -1)) => (WALL_W, WALL_E),
+            Some((0, 1)) => (WALL_E, WALL_W),
             _ => return,
         };
 
@@ -82,6 +100,32 @@ pub trait MazeGenerator {
         step.push(MazeChange { row: row2, col: col2, old: old_c2, new: c2 });
     }
 
+    /// Open only the first cell's side of the wall towards its neighbour.
+    /// Weave tunnels use this to pass under the neighbour.
+    fn remove_wall_toward(
+        &self,
+        maze: &mut Maze,
+        row: u32,
+        col: u32,
+        toward_row: u32,
+        toward_col: u32,
+        step: &mut Vec<MazeChange>,
+    ) {
+        let wall = match maze.direction(row, col, toward_row, toward_col) {
+            Some((-1, 0)) => WALL_N,
+            Some((1, 0)) => WALL_S,
+            Some((0, -1)) => WALL_W,
+            Some((0, 1)) => WALL_E,
+            _ => return,
+        };
+
+        let old = maze.get_cell(row, col);
+        let mut cell = old;
+        cell.remove_wall(wall);
+        maze.set_cell(row, col, cell);
+        step.push(MazeChange { row, col, old, new: cell });
+    }
+
     /// Add the wall between two adjacent cells and record both changes.
     fn add_wall_between(
         &self,
@@ -94,11 +138,11 @@ pub trait MazeGenerator {
     ) {
         let old1 = maze.get_cell(row1, col1);
         let old2 = maze.get_cell(row2, col2);
-        let (w1, w2) = match (row2 as i32 - row1 as i32, col2 as i32 - col1 as i32) {
-            (-1, 0) => (WALL_N, WALL_S),
-            (1, 0) => (WALL_S, WALL_N),
-            (0, -1) => (WALL_W, WALL_E),
-            (0, 1) => (WALL_E, WALL_W),
+        let (w1, w2) = match maze.direction(row1, col1, row2, col2) {
+            Some((-1, 0)) => (WALL_N, WALL_S),
+            Some((1, 0)) => (WALL_S, WALL_N),
+            Some((0, -1)) => (WALL_W, WALL_E),
+            Some((0, 1)) => (WALL_E, WALL_W),
             _ => return,
         };
         let mut c1 = old1;
@@ -121,9 +165,9 @@ pub trait MazeGenerator {
         dc: i32,
         step: &mut Vec<MazeChange>,
     ) {
-        let nr = (row as i32 + dr) as u32;
-        let nc = (col as i32 + dc) as u32;
-        self.remove_wall_between(maze, row, col, nr, nc, step);
+        if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+            self.remove_wall_between(maze, row, col, nr, nc, step);
+        }
     }
 
     /// Add a wall in the specified direction (dr, dc) from the given cell
@@ -136,12 +180,13 @@ pub trait MazeGenerator {
         dc: i32,
         step: &mut Vec<MazeChange>,
     ) {
-        let nr = (row as i32 + dr) as u32;
-        let nc = (col as i32 + dc) as u32;
-        self.add_wall_between(maze, row, col, nr, nc, step);
+        if let Some((nr, nc)) = maze.neighbor(row, col, dr, dc) {
+            self.add_wall_between(maze, row, col, nr, nc, step);
+        }
     }
 
     /// Default method: make all cells into a specific type
+    #[allow(dead_code)]
     fn make_all_into(&self, maze: &mut Maze, cell_type: CellType) -> Vec<MazeChange> {
         let mut changes = Vec::new();
 
@@ -167,7 +212,9 @@ pub trait MazeGenerator {
         changes
     }
 
-    /// Add all 4 walls to every cell
+    /// Add all 4 walls to every cell inside the mask. Disabled cells get
+    /// the sides they share with it, so walls on the edge of the mask are
+    /// stored on both cells like any other.
     fn add_all_walls(&self, maze: &mut Maze) -> Vec<MazeChange> {
         let mut changes = Vec::new();
 
@@ -175,7 +222,18 @@ pub trait MazeGenerator {
             for col in 0..maze.width() {
                 let mut cell = maze.get_cell(row, col);
                 let old = cell;
-                cell.add_wall(WALL_N | WALL_E | WALL_S | WALL_W);
+                if maze.is_enabled(row, col) {
+                    cell.add_wall(WALL_N | WALL_E | WALL_S | WALL_W);
+                } else {
+                    for &(wall, dr, dc) in &[(WALL_N, -1, 0), (WALL_E, 0, 1), (WALL_S, 1, 0), (WALL_W, 0, -1)] {
+                        if maze.enabled_neighbor(row, col, dr, dc).is_some() {
+                            cell.add_wall(wall);
+                        }
+                    }
+                    if cell == old {
+                        continue;
+                    }
+                }
                 maze.set_cell(row, col, cell);
                 changes.push(MazeChange {
                     row,
@@ -189,12 +247,35 @@ pub trait MazeGenerator {
         changes
     }
 
-    /// Remove all walls from every cell
+    /// A random enabled cell that isn't `Visited` or `Current` yet. Walks
+    /// that grow from one cell use it to start over in the next region
+    /// when a mask splits the maze.
+    fn random_unvisited_cell(&self, maze: &Maze) -> Option<(u32, u32)> {
+        let mut cells = Vec::new();
+        for row in 0..maze.height() {
+            for col in 0..maze.width() {
+                let unvisited = !matches!(maze.get_cell(row, col).get_type(), CellType::Visited | CellType::Current);
+                if maze.is_enabled(row, col) && unvisited {
+                    cells.push((row, col));
+                }
+            }
+        }
+
+        if cells.is_empty() {
+            return None;
+        }
+        Some(cells[(utils::random() * cells.len() as f64).floor() as usize])
+    }
+
+    /// Remove all walls from every cell inside the mask
     fn remove_all_walls(&self, maze: &mut Maze) -> Vec<MazeChange> {
         let mut changes = Vec::new();
 
         for row in 0..maze.height() {
             for col in 0..maze.width() {
+                if !maze.is_enabled(row, col) {
+                    continue;
+                }
                 let mut cell = maze.get_cell(row, col);
                 let old = cell;
                 cell.remove_wall(WALL_N | WALL_E | WALL_S | WALL_W);
@@ -238,10 +319,43 @@ pub trait MazeGenerator {
     }
 }
 
+/// The generator called `name`, if there is one
+fn generator_for(name: &str) -> Option<Box<dyn MazeGenerator>> {
+    let generator: Box<dyn MazeGenerator> = match name {
+        "aldous_broder" => Box::new(AdlousBroder::new()),
+        "recursive_division" => Box::new(RecursiveDivision::new()),
+        "kruskals" => Box::new(Kruskals::new()),
+        "kruskals_weave" => Box::new(Kruskals::weave(WEAVE_DENSITY)),
+        "prims" => Box::new(Prims::new()),
+        _ => return None,
+    };
+    Some(generator)
+}
+
+/// Stands in for the generator of a recording that nothing can produce
+/// again, and hands back the recorded steps instead
+struct Recording {
+    steps: Vec<Vec<MazeChange>>,
+    crossings: Vec<(usize, u32, u32)>,
+}
+
+impl MazeGenerator for Recording {
+    fn generate_maze_steps(&mut self, _maze: &Maze) -> Option<Vec<Vec<MazeChange>>> {
+        Some(self.steps.clone())
+    }
+
+    fn crossings(&self) -> Vec<(usize, u32, u32)> {
+        self.crossings.clone()
+    }
+}
+
 #[wasm_bindgen]
 pub struct MazeBuilder {
     generator: Box<dyn MazeGenerator>,
+    name: String,
+    seed: Option<u32>,
     steps: Vec<Vec<MazeChange>>,
+    crossings: Vec<(usize, u32, u32)>,
     current_step: usize,
 }
 
@@ -252,24 +366,27 @@ impl MazeBuilder {
         let generator = Box::new(AdlousBroder::new());
         MazeBuilder {
             generator,
+            name: "aldous_broder".to_string(),
+            seed: None,
             steps: vec![vec![]],
+            crossings: Vec::new(),
             current_step: 0,
         }
     }
 
     #[wasm_bindgen(js_name = "withGenerator")]
     pub fn with_generator(name: &str) -> MazeBuilder {
-        let generator: Box<dyn MazeGenerator> = match name {
-            "aldous_broder" => Box::new(AdlousBroder::new()),
-            "recursive_division" => Box::new(RecursiveDivision::new()),
-            "kruskals" => Box::new(Kruskals::new()),
-            "prims" => Box::new(Prims::new()),
-            _ => Box::new(AdlousBroder::new()), // fallback
+        let generator = match generator_for(name) {
+            Some(generator) => generator,
+            None => return MazeBuilder::new(), // fallback
         };
 
         MazeBuilder {
             generator,
+            name: name.to_string(),
+            seed: None,
             steps: vec![vec![]],
+            crossings: Vec::new(),
             current_step: 0,
         }
     }
@@ -277,16 +394,46 @@ impl MazeBuilder {
     /// Generate all steps for building a maze and store them in steps.
     /// Also set the current step to zero.
     pub fn generate_all(&mut self, maze: &Maze) {
+        utils::seed_random(self.seed);
         self.steps = self.generator.generate_maze_steps(maze).unwrap_throw();
+        self.crossings = self.generator.crossings();
         self.current_step = 0;
+        utils::seed_random(None);
+    }
+
+    /// Name of the generator, as passed to `withGenerator`
+    pub fn generator_name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Seed the next `generate_all` so it can be reproduced exactly.
+    /// `None` (the default) generates a different maze every time.
+    pub fn set_seed(&mut self, seed: Option<u32>) {
+        self.seed = seed;
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
     }
 
     pub fn step_forward(&mut self, maze: &mut Maze) -> bool {
         if self.current_step < self.steps.len() {
+            // A fresh run starts without the crossings or openings of an
+            // earlier one
+            if self.current_step == 0 {
+                maze.clear_under_crossings();
+                maze.forget_openings();
+            }
+
             // Replay saved step
             for change in &self.steps[self.current_step] {
                 maze.set_cell(change.row, change.col, change.new);
             }
+            for &(step, row, col) in &self.crossings {
+                if step == self.current_step {
+                    maze.set_under_crossing(row, col, true);
+                }
+            }
             self.current_step += 1;
             return true;
         }
@@ -304,6 +451,11 @@ impl MazeBuilder {
         for change in &self.steps[self.current_step] {
             maze.set_cell(change.row, change.col, change.old);
         }
+        for &(step, row, col) in &self.crossings {
+            if step == self.current_step {
+                maze.set_under_crossing(row, col, false);
+            }
+        }
 
         true
     }
@@ -327,3 +479,93 @@ impl MazeBuilder {
         }
     }
 }
+
+impl MazeBuilder {
+    /// The maze as it looks once steps `0..=step` are applied, leaving the
+    /// builder where it is
+    pub fn maze_at(&self, maze: &Maze, step: usize) -> Maze {
+        let mut maze = maze.clone();
+        maze.clear_under_crossings();
+        maze.forget_openings();
+        for i in 0..=step.min(self.steps.len()) {
+            self.apply_step(&mut maze, i);
+        }
+        maze
+    }
+
+    /// Replay one step on `maze` without moving the builder
+    pub fn apply_step(&self, maze: &mut Maze, step: usize) {
+        for change in self.step_changes(step) {
+            maze.set_cell(change.row, change.col, change.new);
+        }
+        for &(at, row, col) in &self.crossings {
+            if at == step {
+                maze.set_under_crossing(row, col, true);
+            }
+        }
+    }
+
+    pub fn steps(&self) -> &[Vec<MazeChange>] {
+        &self.steps
+    }
+
+    /// Weave crossings of the recorded run, as `(step, row, col)`
+    pub fn crossings(&self) -> &[(usize, u32, u32)] {
+        &self.crossings
+    }
+
+    pub fn parameters(&self) -> Vec<(String, f64)> {
+        self.generator.parameters()
+    }
+
+    /// A builder that plays back a recorded run instead of generating one.
+    /// If `name` is a generator it is set up from `parameters`, so
+    /// `generate_all` makes a fresh maze the same way. Any other name, like
+    /// a distance flood, can't be regenerated and `generate_all` just
+    /// rewinds to the recorded run.
+    pub fn from_recording(
+        name: &str,
+        parameters: &[(String, f64)],
+        seed: Option<u32>,
+        steps: Vec<Vec<MazeChange>>,
+        crossings: Vec<(usize, u32, u32)>,
+    ) -> MazeBuilder {
+        let mut generator = generator_for(name).unwrap_or_else(|| {
+            Box::new(Recording { steps: steps.clone(), crossings: crossings.clone() })
+        });
+        for (parameter, value) in parameters {
+            generator.set_parameter(parameter, *value);
+        }
+
+        MazeBuilder {
+            generator,
+            name: name.to_string(),
+            seed,
+            steps,
+            crossings,
+            current_step: 0,
+        }
+    }
+
+    /// Changes made by one step, empty past the end
+    pub fn step_changes(&self, step: usize) -> &[MazeChange] {
+        self.steps.get(step).map_or(&[], |changes| changes.as_slice())
+    }
+}
+
+/// Every name `MazeBuilder::with_generator` knows
+#[cfg(test)]
+pub const GENERATOR_NAMES: [&str; 5] = ["aldous_broder", "recursive_division", "kruskals", "kruskals_weave", "prims"];
+
+#[cfg(test)]
+impl MazeBuilder {
+    /// Run the generator `name` on `maze` with a fixed seed and return the
+    /// builder together with the finished maze
+    pub fn generated(name: &str, seed: u32, maze: &Maze) -> (MazeBuilder, Maze) {
+        let mut builder = MazeBuilder::with_generator(name);
+        builder.set_seed(Some(seed));
+        builder.generate_all(maze);
+        let finished = builder.maze_at(maze, builder.total_steps());
+        (builder, finished)
+    }
+}
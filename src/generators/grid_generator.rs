@@ -0,0 +1,292 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    generators::builders::{
+        AdlousBroder,
+        Kruskals,
+        Prims
+    },
+    grid::{GridCell, GridChange, GridMaze, Link},
+    delta::DeltaMaze,
+    hex::HexMaze,
+    maze3d::Maze3D,
+    polar::PolarMaze,
+    utils
+};
+
+
+/// Counterpart of `MazeGenerator` for grids described by a `Topology`.
+///
+/// Generators work on a copy of the cells and only ever address them by
+/// index, so the same algorithm runs on hexagons, rings or anything else
+/// that can list its neighbours.
+pub trait GridGenerator {
+    fn generate_grid_steps(&mut self, grid: &dyn GridMaze) -> Option<Vec<Vec<GridChange>>>;
+
+    /// Default method: mark a cell with a new type and record the change
+    fn mark_grid_cell(
+        &self,
+        cells: &mut [GridCell],
+        index: usize,
+        cell_type: CellType,
+        step: &mut Vec<GridChange>,
+    ) {
+        let old = cells[index];
+        let mut new = old;
+        new.set_type(cell_type);
+
+        if old != new {
+            cells[index] = new;
+            step.push(GridChange { index: index as u32, old, new });
+        }
+    }
+
+    /// Default method: open the passage described by `link`
+    fn remove_grid_wall(
+        &self,
+        cells: &mut [GridCell],
+        index: usize,
+        link: Link,
+        step: &mut Vec<GridChange>,
+    ) {
+        let old_c1 = cells[index];
+        let old_c2 = cells[link.neighbor];
+
+        let mut c1 = old_c1;
+        let mut c2 = old_c2;
+        c1.remove_wall(link.side);
+        c2.remove_wall(link.back);
+
+        cells[index] = c1;
+        cells[link.neighbor] = c2;
+
+        step.push(GridChange { index: index as u32, old: old_c1, new: c1 });
+        step.push(GridChange { index: link.neighbor as u32, old: old_c2, new: c2 });
+    }
+
+    /// Close every side of every cell
+    fn add_all_grid_walls(&self, grid: &dyn GridMaze, cells: &mut [GridCell]) -> Vec<GridChange> {
+        let mut changes = Vec::new();
+
+        for (index, cell) in cells.iter_mut().enumerate() {
+            let old = *cell;
+            cell.set_walls((1u16 << grid.side_count(index)) - 1);
+            if old != *cell {
+                changes.push(GridChange { index: index as u32, old, new: *cell });
+            }
+        }
+
+        changes
+    }
+
+    // Set all visited cells to default state
+    fn set_all_grid_visited_to_default(&self, cells: &mut [GridCell]) -> Vec<GridChange> {
+        let mut changes = Vec::new();
+
+        for index in 0..cells.len() {
+            if cells[index].get_type() == CellType::Visited {
+                self.mark_grid_cell(cells, index, CellType::Default, &mut changes);
+            }
+        }
+
+        changes
+    }
+}
+
+/// Look up a generator that can run on any `Topology`.
+pub fn grid_generator(name: &str) -> Box<dyn GridGenerator> {
+    match name {
+        "aldous_broder" => Box::new(AdlousBroder::new()),
+        "kruskals" => Box::new(Kruskals::new()),
+        "prims" => Box::new(Prims::new()),
+        _ => Box::new(AdlousBroder::new()), // fallback
+    }
+}
+
+/// Every name `grid_generator` knows
+#[cfg(test)]
+pub const GRID_GENERATOR_NAMES: [&str; 3] = ["aldous_broder", "kruskals", "prims"];
+
+/// Run every grid generator on `grid` with a few seeds and check that each
+/// one carves a spanning tree
+#[cfg(test)]
+pub fn assert_generates_spanning_trees<G: GridMaze + Clone>(grid: &G) {
+    for name in GRID_GENERATOR_NAMES.iter() {
+        for seed in 1..4 {
+            let mut steps = GridSteps::new(name);
+            steps.set_seed(Some(seed));
+            steps.generate_all(grid);
+
+            let mut maze = grid.clone();
+            steps.step_to(steps.total_steps(), &mut maze);
+            crate::grid::assert_spanning_tree(&maze);
+        }
+    }
+}
+
+/// Recorded generation steps that can be played back and forth on a grid.
+pub struct GridSteps {
+    generator: Box<dyn GridGenerator>,
+    seed: Option<u32>,
+    steps: Vec<Vec<GridChange>>,
+    current_step: usize,
+}
+
+impl GridSteps {
+    pub fn new(name: &str) -> GridSteps {
+        GridSteps {
+            generator: grid_generator(name),
+            seed: None,
+            steps: vec![vec![]],
+            current_step: 0,
+        }
+    }
+
+    pub fn generate_all(&mut self, grid: &dyn GridMaze) {
+        utils::seed_random(self.seed);
+        self.steps = self.generator.generate_grid_steps(grid).unwrap_throw();
+        self.current_step = 0;
+        utils::seed_random(None);
+    }
+
+    pub fn set_seed(&mut self, seed: Option<u32>) {
+        self.seed = seed;
+    }
+
+    pub fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+
+    pub fn step_forward(&mut self, grid: &mut dyn GridMaze) -> bool {
+        if self.current_step < self.steps.len() {
+            for change in &self.steps[self.current_step] {
+                grid.set_grid_cell(change.index as usize, change.new);
+            }
+            self.current_step += 1;
+            return true;
+        }
+
+        false
+    }
+
+    pub fn step_backward(&mut self, grid: &mut dyn GridMaze) -> bool {
+        if self.current_step == 0 {
+            return false;
+        }
+
+        self.current_step -= 1;
+        for change in &self.steps[self.current_step] {
+            grid.set_grid_cell(change.index as usize, change.old);
+        }
+
+        true
+    }
+
+    pub fn total_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.current_step
+    }
+
+    pub fn reset(&mut self) {
+        self.current_step = 0;
+    }
+
+    pub fn step_to(&mut self, target: usize, grid: &mut dyn GridMaze) {
+        self.reset();
+        for _ in 0..=target {
+            self.step_forward(grid);
+        }
+    }
+}
+
+/// A wasm builder for one grid type, the counterpart of `MazeBuilder`
+macro_rules! grid_builder {
+    ($builder:ident, $grid:ty) => {
+        #[wasm_bindgen]
+        pub struct $builder {
+            steps: GridSteps,
+        }
+
+        #[wasm_bindgen]
+        impl $builder {
+            #[wasm_bindgen(constructor)]
+            pub fn new() -> $builder {
+                $builder { steps: GridSteps::new("aldous_broder") }
+            }
+
+            /// Supports `aldous_broder`, `kruskals` and `prims`
+            #[wasm_bindgen(js_name = "withGenerator")]
+            pub fn with_generator(name: &str) -> $builder {
+                $builder { steps: GridSteps::new(name) }
+            }
+
+            pub fn generate_all(&mut self, maze: &$grid) {
+                self.steps.generate_all(maze);
+            }
+
+            /// Seed the next `generate_all` so it can be reproduced exactly.
+            /// `None` (the default) generates a different maze every time.
+            pub fn set_seed(&mut self, seed: Option<u32>) {
+                self.steps.set_seed(seed);
+            }
+
+            pub fn seed(&self) -> Option<u32> {
+                self.steps.seed()
+            }
+
+            pub fn step_forward(&mut self, maze: &mut $grid) -> bool {
+                self.steps.step_forward(maze)
+            }
+
+            pub fn step_backward(&mut self, maze: &mut $grid) -> bool {
+                self.steps.step_backward(maze)
+            }
+
+            pub fn total_steps(&self) -> usize {
+                self.steps.total_steps()
+            }
+
+            pub fn current_step(&self) -> usize {
+                self.steps.current_step()
+            }
+
+            pub fn reset(&mut self) {
+                self.steps.reset();
+            }
+
+            pub fn step_to(&mut self, target: usize, maze: &mut $grid) {
+                self.steps.step_to(target, maze);
+            }
+        }
+    };
+}
+
+grid_builder!(HexMazeBuilder, HexMaze);
+grid_builder!(PolarMazeBuilder, PolarMaze);
+grid_builder!(DeltaMazeBuilder, DeltaMaze);
+grid_builder!(Maze3DBuilder, Maze3D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let maze = HexMaze::new(6, 5);
+        let mut first = HexMazeBuilder::with_generator("prims");
+        let mut second = HexMazeBuilder::with_generator("prims");
+        first.set_seed(Some(7));
+        second.set_seed(Some(7));
+        first.generate_all(&maze);
+        second.generate_all(&maze);
+
+        let (mut a, mut b) = (maze.clone(), maze.clone());
+        first.step_to(first.total_steps(), &mut a);
+        second.step_to(second.total_steps(), &mut b);
+        assert_eq!(a.grid_cells(), b.grid_cells());
+    }
+}
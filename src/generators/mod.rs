@@ -1,2 +1,10 @@
 mod generator;
 mod builders;
+mod grid_generator;
+
+pub use generator::MazeBuilder;
+pub use builders::UnionFind;
+#[cfg(test)]
+pub use generator::GENERATOR_NAMES;
+#[cfg(test)]
+pub use grid_generator::assert_generates_spanning_trees;
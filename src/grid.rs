@@ -0,0 +1,149 @@
+use wasm_bindgen::prelude::*;
+
+use crate::cell::CellType;
+
+/// Up to 8 walls live in the low byte, the `CellType` nibble sits right above it.
+pub const GRID_WALL_MASK: u16 = 0x00FF;
+pub const GRID_TYPE_MASK: u16 = 0xFF00;
+
+/// A cell for grids whose cells have more than four sides.
+///
+/// The square `Cell` packs four walls and the type into a `u8`, which leaves
+/// no room for hexagons or polar cells. Bit `i` of the wall byte is side `i`
+/// as numbered by the grid the cell belongs to.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridCell {
+    value: u16,
+}
+
+#[wasm_bindgen]
+impl GridCell {
+    /// Get the cell type
+    pub fn get_type(&self) -> CellType {
+        CellType::from_bits((self.value >> 4) as u8)
+    }
+}
+
+impl GridCell {
+    /// Create a new GridCell with a specific type and no walls
+    pub fn new(cell_type: CellType) -> GridCell {
+        GridCell {
+            value: (cell_type as u16) << 4,
+        }
+    }
+
+    /// Get the raw u16 representation
+    pub fn raw(&self) -> u16 {
+        self.value
+    }
+
+    /// Set the cell type
+    pub fn set_type(&mut self, cell_type: CellType) {
+        self.value = (self.value & GRID_WALL_MASK) | ((cell_type as u16) << 4);
+    }
+
+    /// Check if the wall on `side` exists
+    pub fn has_wall(&self, side: u8) -> bool {
+        self.value & (1 << side) != 0
+    }
+
+    /// Add the wall on `side`
+    pub fn add_wall(&mut self, side: u8) {
+        self.value |= 1 << side;
+    }
+
+    /// Remove the wall on `side`
+    pub fn remove_wall(&mut self, side: u8) {
+        self.value &= !(1 << side);
+    }
+
+    /// Get every wall bit at once
+    pub fn walls(&self) -> u16 {
+        self.value & GRID_WALL_MASK
+    }
+
+    /// Set every wall bit at once
+    pub fn set_walls(&mut self, walls: u16) {
+        self.value = (self.value & GRID_TYPE_MASK) | (walls & GRID_WALL_MASK);
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct GridChange {
+    pub index: u32,
+    pub old: GridCell,
+    pub new: GridCell,
+}
+
+/// One passage a cell could open: the wall on `side` of the cell is shared
+/// with the wall on `back` of `neighbor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub side: u8,
+    pub neighbor: usize,
+    pub back: u8,
+}
+
+/// The shape of a non-square maze: how many cells it has and which of them
+/// touch. Generators only ever talk to a grid through this.
+pub trait Topology {
+    fn cell_count(&self) -> usize;
+
+    /// Number of wall bits a cell uses, including sides on the outer boundary
+    fn side_count(&self, index: usize) -> u8;
+
+    /// Every neighbour the cell shares a wall with
+    fn links(&self, index: usize) -> Vec<Link>;
+}
+
+/// A topology that also owns its cells.
+pub trait GridMaze: Topology {
+    fn grid_cells(&self) -> &[GridCell];
+
+    fn set_grid_cell(&mut self, index: usize, cell: GridCell);
+}
+
+/// Every link must be matched by one from the neighbour back through the
+/// `back` side, or generators would open half a passage.
+#[cfg(test)]
+pub fn assert_links_match(grid: &dyn Topology) {
+    for index in 0..grid.cell_count() {
+        for link in grid.links(index) {
+            assert!(link.side < grid.side_count(index), "side {} of cell {}", link.side, index);
+            let back = Link { side: link.back, neighbor: index, back: link.side };
+            assert!(grid.links(link.neighbor).contains(&back), "{:?} from cell {} has no way back", link, index);
+        }
+    }
+}
+
+/// The open links of a generated grid must form a spanning tree: every
+/// cell reachable, one passage less than there are cells, and each
+/// passage open from both sides.
+#[cfg(test)]
+pub fn assert_spanning_tree(grid: &dyn GridMaze) {
+    let cells = grid.grid_cells();
+    let mut seen = vec![false; cells.len()];
+    let mut stack = vec![0];
+    let mut passages = 0;
+    seen[0] = true;
+
+    while let Some(index) = stack.pop() {
+        for link in grid.links(index) {
+            let open = !cells[index].has_wall(link.side);
+            assert_eq!(open, !cells[link.neighbor].has_wall(link.back), "half-open {:?} from cell {}", link, index);
+            if open && link.neighbor > index {
+                passages += 1;
+            }
+            if open && !seen[link.neighbor] {
+                seen[link.neighbor] = true;
+                stack.push(link.neighbor);
+            }
+        }
+    }
+
+    assert!(seen.iter().all(|&seen| seen), "not every cell is reachable");
+    assert_eq!(passages, cells.len() - 1);
+}
+
@@ -0,0 +1,195 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    grid::{GridCell, GridMaze, Link, Topology},
+    utils
+};
+
+// Sides of a pointy-top hexagon, clockwise from the upper right edge
+pub const HEX_NE: u8 = 0;
+pub const HEX_E: u8 = 1;
+pub const HEX_SE: u8 = 2;
+pub const HEX_SW: u8 = 3;
+pub const HEX_W: u8 = 4;
+pub const HEX_NW: u8 = 5;
+
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+/// A maze of pointy-top hexagons laid out in rows, where every odd row is
+/// shifted half a cell to the right.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct HexMaze {
+    width: u32,
+    height: u32,
+    cells: Vec<GridCell>,
+}
+
+#[wasm_bindgen]
+impl HexMaze {
+    pub fn new(width: u32, height: u32) -> HexMaze {
+        utils::set_panic_hook();
+
+        HexMaze {
+            width,
+            height,
+            cells: vec![GridCell::new(CellType::Default); (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pointer to the `u16` cell values, row by row
+    pub fn cells(&self) -> *const GridCell {
+        self.cells.as_ptr()
+    }
+
+    /// Center of a cell for hexagons with circumradius `size`
+    pub fn cell_center(&self, row: u32, col: u32, size: f64) -> Vec<f64> {
+        let offset = if row % 2 == 1 { 0.5 } else { 0.0 };
+        let x = SQRT_3 * size * (col as f64 + offset + 0.5);
+        let y = size * (1.5 * row as f64 + 1.0);
+        vec![x, y]
+    }
+
+    /// The six corners of a cell as `[x0, y0, x1, y1, ...]`, starting at the
+    /// top and going clockwise. Side `i` runs from corner `i` to corner `i + 1`.
+    pub fn cell_vertices(&self, row: u32, col: u32, size: f64) -> Vec<f64> {
+        let center = self.cell_center(row, col, size);
+        let mut vertices = Vec::with_capacity(12);
+        for corner in 0..6 {
+            let angle = (60.0 * corner as f64 - 90.0).to_radians();
+            vertices.push(center[0] + size * angle.cos());
+            vertices.push(center[1] + size * angle.sin());
+        }
+        vertices
+    }
+
+    /// Width of the canvas needed to draw the maze
+    pub fn pixel_width(&self, size: f64) -> f64 {
+        let offset = if self.height > 1 { 0.5 } else { 0.0 };
+        SQRT_3 * size * (self.width as f64 + offset)
+    }
+
+    /// Height of the canvas needed to draw the maze
+    pub fn pixel_height(&self, size: f64) -> f64 {
+        size * (1.5 * self.height as f64 + 0.5)
+    }
+
+    fn get_index(&self, row: u32, col: u32) -> usize {
+        assert!(row < self.height, "Row out of bounds: {} >= {}", row, self.height);
+        assert!(col < self.width, "Column out of bounds: {} >= {}", col, self.width);
+        (row * self.width + col) as usize
+    }
+}
+
+impl HexMaze {
+    pub fn get_cell(&self, row: u32, col: u32) -> GridCell {
+        self.cells[self.get_index(row, col)]
+    }
+
+    pub fn set_cell(&mut self, row: u32, col: u32, cell: GridCell) {
+        let idx = self.get_index(row, col);
+        self.cells[idx] = cell;
+    }
+
+    /// Row and column of the cell across `side`, if it is inside the maze
+    pub fn neighbor(&self, row: u32, col: u32, side: u8) -> Option<(u32, u32)> {
+        let (row, col) = (row as i64, col as i64);
+        // Odd rows are shifted right, so their diagonal neighbours are too
+        let shift = row & 1;
+        let (nr, nc) = match side {
+            HEX_NE => (row - 1, col + shift),
+            HEX_E => (row, col + 1),
+            HEX_SE => (row + 1, col + shift),
+            HEX_SW => (row + 1, col + shift - 1),
+            HEX_W => (row, col - 1),
+            HEX_NW => (row - 1, col + shift - 1),
+            _ => return None,
+        };
+
+        if nr < 0 || nc < 0 || nr >= self.height as i64 || nc >= self.width as i64 {
+            return None;
+        }
+        Some((nr as u32, nc as u32))
+    }
+}
+
+impl Topology for HexMaze {
+    fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn side_count(&self, _index: usize) -> u8 {
+        6
+    }
+
+    fn links(&self, index: usize) -> Vec<Link> {
+        let row = index as u32 / self.width;
+        let col = index as u32 % self.width;
+
+        (0..6)
+            .filter_map(|side| {
+                self.neighbor(row, col, side).map(|(nr, nc)| Link {
+                    side,
+                    neighbor: (nr * self.width + nc) as usize,
+                    back: (side + 3) % 6,
+                })
+            })
+            .collect()
+    }
+}
+
+impl GridMaze for HexMaze {
+    fn grid_cells(&self) -> &[GridCell] {
+        &self.cells
+    }
+
+    fn set_grid_cell(&mut self, index: usize, cell: GridCell) {
+        self.cells[index] = cell;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generators::assert_generates_spanning_trees, grid::assert_links_match};
+
+    #[test]
+    fn finds_six_neighbours_inside_and_fewer_on_the_edge() {
+        let maze = HexMaze::new(5, 4);
+        assert_eq!(maze.links(2 * 5 + 2).len(), 6);
+        assert_eq!(maze.links(0).len(), 2);
+        // The shifted odd rows have a neighbour up and down on the right
+        assert_eq!(maze.neighbor(1, 3, HEX_NE), Some((0, 4)));
+        assert_eq!(maze.neighbor(1, 4, HEX_NE), None);
+        assert_eq!(maze.neighbor(2, 0, HEX_NW), None);
+        assert_links_match(&maze);
+    }
+
+    #[test]
+    fn neighbours_share_a_side() {
+        let maze = HexMaze::new(3, 3);
+        let size = 10.0;
+        let (a, b) = (maze.cell_vertices(1, 1, size), maze.cell_vertices(2, 2, size));
+        assert_eq!(maze.neighbor(1, 1, HEX_SE), Some((2, 2)));
+        // Side HEX_SE of (1, 1) runs along side HEX_NW of its neighbour
+        let side = |v: &[f64], s: usize| [v[2 * s], v[2 * s + 1], v[(2 * s + 2) % 12], v[(2 * s + 3) % 12]];
+        let (se, nw) = (side(&a, HEX_SE as usize), side(&b, HEX_NW as usize));
+        for (p, q) in se.iter().zip(&[nw[2], nw[3], nw[0], nw[1]]) {
+            assert!((p - q).abs() < 1e-9, "{:?} {:?}", se, nw);
+        }
+    }
+
+    #[test]
+    fn every_generator_carves_a_spanning_tree() {
+        assert_generates_spanning_trees(&HexMaze::new(7, 5));
+    }
+}
@@ -1,4 +1,19 @@
 mod utils;
 mod cell;
 mod maze;
+mod grid;
+mod hex;
+mod polar;
+mod delta;
+mod maze3d;
 mod generators;
+mod formats;
+mod render;
+mod analysis;
+mod validate;
+mod distance;
+mod placement;
+
+pub use cell::{Cell, CellType, WALL_E, WALL_N, WALL_S, WALL_W};
+pub use formats::FormatError;
+pub use maze::{Maze, Opening};
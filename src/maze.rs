@@ -1,7 +1,7 @@
 use std::{convert::TryInto, fmt};
 use wasm_bindgen::prelude::*;
 
-use crate::{cell::{Cell, CellType}, utils};
+use crate::{cell::{Cell, CellType, WALL_E, WALL_N, WALL_S, WALL_W}, utils};
 
 #[wasm_bindgen]
 #[derive(Clone)]
@@ -9,6 +9,21 @@ pub struct Maze {
     width: u32,
     height: u32,
     cells: Vec<Cell>,
+    wrap_horizontal: bool,
+    wrap_vertical: bool,
+    // Cells a weave passage tunnels under
+    under: Vec<bool>,
+    // Outer walls opened as the way in and out, as (row, col, wall)
+    entrance: Option<(u32, u32, u8)>,
+    exit: Option<(u32, u32, u8)>,
+}
+
+/// An opening carved through the outer wall of the maze
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opening {
+    Entrance,
+    Exit,
 }
 
 #[wasm_bindgen]
@@ -20,9 +35,163 @@ impl Maze {
             width,
             height,
             cells: vec![Cell::new(CellType::Default); (width * height).try_into().unwrap()],
+            wrap_horizontal: false,
+            wrap_vertical: false,
+            under: vec![false; (width * height) as usize],
+            entrance: None,
+            exit: None,
         }
     }
 
+    /// Connect the east edge to the west edge (a cylinder) and/or the north
+    /// edge to the south edge (together a torus).
+    ///
+    /// Wrapping only takes effect along a side that is at least 3 cells long,
+    /// otherwise the seam would join cells that already touch.
+    pub fn set_wrap(&mut self, horizontal: bool, vertical: bool) {
+        self.wrap_horizontal = horizontal;
+        self.wrap_vertical = vertical;
+    }
+
+    /// Whether the east and west edges are joined
+    pub fn wraps_horizontally(&self) -> bool {
+        self.wrap_horizontal && self.width > 2
+    }
+
+    /// Whether the north and south edges are joined
+    pub fn wraps_vertically(&self) -> bool {
+        self.wrap_vertical && self.height > 2
+    }
+
+    /// Disable every cell whose bit is clear in `bits`, one bit per cell in
+    /// row-major order starting at the lowest bit of the first byte.
+    pub fn set_mask_bits(&mut self, bits: &[u8]) {
+        let count = self.cells.len();
+        assert!(bits.len() * 8 >= count, "Mask too short: {} bits < {} cells", bits.len() * 8, count);
+
+        let mask: Vec<bool> = (0..count).map(|i| bits[i / 8] & (1 << (i % 8)) != 0).collect();
+        self.apply_mask(&mask);
+    }
+
+    /// Resize the maze to the ASCII art and disable every cell drawn as a
+    /// space or `.`. Any other character marks a cell inside the shape.
+    pub fn set_mask_ascii(&mut self, art: &str) {
+        let lines: Vec<&str> = art.lines().collect();
+        self.width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+        self.height = lines.len() as u32;
+
+        let mut mask = Vec::with_capacity((self.width * self.height) as usize);
+        for line in &lines {
+            let mut chars = line.chars();
+            for _ in 0..self.width {
+                mask.push(!matches!(chars.next(), None | Some(' ') | Some('.')));
+            }
+        }
+
+        self.cells = vec![Cell::new(CellType::Default); mask.len()];
+        self.under = vec![false; mask.len()];
+        self.entrance = None;
+        self.exit = None;
+        self.apply_mask(&mask);
+    }
+
+    /// Disable cells using a monochrome image with one byte per pixel,
+    /// scaled to the size of the maze. Dark pixels (below 128) are inside.
+    pub fn set_mask_image(&mut self, pixels: &[u8], image_width: u32, image_height: u32) {
+        assert!(
+            pixels.len() >= (image_width * image_height) as usize,
+            "Image too short: {} bytes for {}x{} pixels", pixels.len(), image_width, image_height
+        );
+
+        let mut mask = Vec::with_capacity(self.cells.len());
+        for row in 0..self.height {
+            for col in 0..self.width {
+                // Sample the pixel under the center of the cell
+                let x = ((col as f64 + 0.5) * image_width as f64 / self.width as f64) as u32;
+                let y = ((row as f64 + 0.5) * image_height as f64 / self.height as f64) as u32;
+                mask.push(pixels[(y * image_width + x) as usize] < 128);
+            }
+        }
+        self.apply_mask(&mask);
+    }
+
+    /// Whether the cell is inside the mask
+    pub fn is_enabled(&self, row: u32, col: u32) -> bool {
+        self.get_cell(row, col).get_type() != CellType::Disabled
+    }
+
+    /// Whether a weave passage tunnels under this cell.
+    ///
+    /// The cell's own walls describe the passage on top. The tunnel runs
+    /// across the two sides that are walled on this cell but open on the
+    /// neighbours facing it.
+    pub fn is_under_crossing(&self, row: u32, col: u32) -> bool {
+        self.under[self.get_index(row, col)]
+    }
+
+    /// Whether the open `wall` of this cell is a passage that crosses the
+    /// seam and comes out on the opposite edge of the maze.
+    pub fn crosses_seam(&self, row: u32, col: u32, wall: u8) -> bool {
+        if self.get_cell(row, col).has_wall(wall) {
+            return false;
+        }
+
+        match wall {
+            WALL_N => self.wraps_vertically() && row == 0,
+            WALL_S => self.wraps_vertically() && row + 1 == self.height,
+            WALL_W => self.wraps_horizontally() && col == 0,
+            WALL_E => self.wraps_horizontally() && col + 1 == self.width,
+            _ => false,
+        }
+    }
+
+    /// Open the outer `wall` of a cell as the entrance or exit, closing the
+    /// one it replaces. Throws if the wall doesn't lead out of the maze.
+    #[wasm_bindgen(js_name = "set_opening")]
+    pub fn set_opening_js(&mut self, opening: Opening, row: u32, col: u32, wall: u8) -> Result<(), JsValue> {
+        if row >= self.height || col >= self.width || !self.faces_outside(row, col, wall) {
+            return Err(JsValue::from_str(&format!(
+                "Wall {:#06b} of ({}, {}) is not on the outside of the maze", wall, row, col
+            )));
+        }
+        self.set_opening(opening, row, col, wall);
+        Ok(())
+    }
+
+    /// Close the entrance or exit again
+    pub fn clear_opening(&mut self, opening: Opening) {
+        let previous = match opening {
+            Opening::Entrance => self.entrance.take(),
+            Opening::Exit => self.exit.take(),
+        };
+        if let Some((row, col, wall)) = previous {
+            if !self.is_opening(row, col, wall) {
+                self.get_cell_mut(row, col).add_wall(wall);
+            }
+        }
+    }
+
+    /// `[row, col, wall]` of the entrance or exit, empty if there is none
+    pub fn opening(&self, opening: Opening) -> Vec<u32> {
+        match self.get_opening(opening) {
+            Some((row, col, wall)) => vec![row, col, wall as u32],
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether `wall` of the cell leads out of the maze: a side on the
+    /// edge that doesn't wrap, or one towards a disabled cell.
+    pub fn faces_outside(&self, row: u32, col: u32, wall: u8) -> bool {
+        let (dr, dc) = match wall {
+            WALL_N => (-1, 0),
+            WALL_S => (1, 0),
+            WALL_W => (0, -1),
+            WALL_E => (0, 1),
+            _ => return false,
+        };
+        self.is_enabled(row, col) && self.enabled_neighbor(row, col, dr, dc).is_none()
+    }
+
     /// Toggle cells to be wall or empty
     pub fn toggle_cell(&mut self, row: u32, col: u32) {
         let idx = self.get_index(row, col);
@@ -37,6 +206,9 @@ impl Maze {
         self.cells = (0..width * self.height)
             .map(|_i| Cell::new(CellType::Default))
             .collect();
+        self.under = vec![false; self.cells.len()];
+        self.entrance = None;
+        self.exit = None;
     }
 
     /// Set the height of the maze.
@@ -47,6 +219,9 @@ impl Maze {
         self.cells = (0..self.width * height)
             .map(|_i| Cell::new(CellType::Default))
             .collect();
+        self.under = vec![false; self.cells.len()];
+        self.entrance = None;
+        self.exit = None;
     }
 
     pub fn render(&self) -> String {
@@ -101,6 +276,172 @@ impl Maze {
         let idx = self.get_index(row, col);
         &mut self.cells[idx]
     }
+
+    pub fn set_under_crossing(&mut self, row: u32, col: u32, under: bool) {
+        let idx = self.get_index(row, col);
+        self.under[idx] = under;
+    }
+
+    pub fn clear_under_crossings(&mut self) {
+        self.under = vec![false; self.cells.len()];
+    }
+
+    /// Open the outer `wall` of a cell as the entrance or exit, closing the
+    /// one it replaces. Generators that rebuild the outer walls close it
+    /// again, so carve openings once the maze is finished.
+    pub fn set_opening(&mut self, opening: Opening, row: u32, col: u32, wall: u8) {
+        assert!(
+            self.faces_outside(row, col, wall),
+            "Wall {:#06b} of ({}, {}) is not on the outside of the maze", wall, row, col
+        );
+
+        self.clear_opening(opening);
+        self.get_cell_mut(row, col).remove_wall(wall);
+        match opening {
+            Opening::Entrance => self.entrance = Some((row, col, wall)),
+            Opening::Exit => self.exit = Some((row, col, wall)),
+        }
+    }
+
+    /// Forget the entrance and exit without touching any wall. A generator
+    /// run rebuilds the outer walls, so the openings it starts from are
+    /// closed by the time it is done.
+    pub fn forget_openings(&mut self) {
+        self.entrance = None;
+        self.exit = None;
+    }
+
+    pub fn get_opening(&self, opening: Opening) -> Option<(u32, u32, u8)> {
+        match opening {
+            Opening::Entrance => self.entrance,
+            Opening::Exit => self.exit,
+        }
+    }
+
+    /// Whether this outer wall is the entrance or the exit
+    pub fn is_opening(&self, row: u32, col: u32, wall: u8) -> bool {
+        self.entrance == Some((row, col, wall)) || self.exit == Some((row, col, wall))
+    }
+
+    /// Every cell reachable in one move from this one, following open walls
+    /// and passing through weave tunnels to the cell on the far side.
+    pub fn passages(&self, row: u32, col: u32) -> Vec<(u32, u32)> {
+        let cell = self.get_cell(row, col);
+        let mut passages = Vec::new();
+
+        for &(wall, back, dr, dc) in &[
+            (WALL_N, WALL_S, -1, 0),
+            (WALL_S, WALL_N, 1, 0),
+            (WALL_W, WALL_E, 0, -1),
+            (WALL_E, WALL_W, 0, 1),
+        ] {
+            if cell.has_wall(wall) {
+                continue;
+            }
+            let (nr, nc) = match self.neighbor(row, col, dr, dc) {
+                Some(neighbor) => neighbor,
+                None => continue,
+            };
+
+            // An open wall into a closed one leads under the neighbour
+            if self.is_under_crossing(nr, nc) && self.get_cell(nr, nc).has_wall(back) {
+                if let Some(far) = self.neighbor(nr, nc, dr, dc) {
+                    passages.push(far);
+                }
+            } else {
+                passages.push((nr, nc));
+            }
+        }
+
+        passages
+    }
+
+    /// Turn the cells where `mask` is false into `CellType::Disabled` and
+    /// reset the rest to `CellType::Default`. Walls are kept.
+    pub fn apply_mask(&mut self, mask: &[bool]) {
+        assert_eq!(mask.len(), self.cells.len(), "Mask size does not match the maze");
+
+        for (cell, &enabled) in self.cells.iter_mut().zip(mask) {
+            cell.set_type(if enabled { CellType::Default } else { CellType::Disabled });
+        }
+    }
+
+    /// Number of cells inside the mask
+    pub fn enabled_cells(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.get_type() != CellType::Disabled).count()
+    }
+
+    /// A random cell inside the mask, or `None` if every cell is disabled
+    pub fn random_enabled_cell(&self) -> Option<(u32, u32)> {
+        if self.enabled_cells() == 0 {
+            return None;
+        }
+
+        loop {
+            let (row, col) = utils::choose_random_cell(self.height, self.width);
+            if self.is_enabled(row, col) {
+                return Some((row, col));
+            }
+        }
+    }
+
+    /// Number of enabled cells that can be reached from this one by stepping
+    /// between enabled neighbours, ignoring walls. A mask can split the maze
+    /// into several such regions, and generators fill each one separately.
+    pub fn region_size(&self, row: u32, col: u32) -> usize {
+        let mut seen = vec![false; self.cells.len()];
+        let mut stack = vec![(row, col)];
+        seen[self.get_index(row, col)] = true;
+        let mut size = 0;
+
+        while let Some((r, c)) = stack.pop() {
+            size += 1;
+            for &(dr, dc) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if let Some((nr, nc)) = self.neighbor(r, c, dr, dc) {
+                    let idx = self.get_index(nr, nc);
+                    if !seen[idx] && self.is_enabled(nr, nc) {
+                        seen[idx] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+        }
+
+        size
+    }
+
+    /// The cell one step of (dr, dc) away, wrapping around the seams if the
+    /// maze wraps. Returns `None` when the step leaves the maze.
+    pub fn neighbor(&self, row: u32, col: u32, dr: i32, dc: i32) -> Option<(u32, u32)> {
+        let mut nr = row as i64 + dr as i64;
+        let mut nc = col as i64 + dc as i64;
+
+        if self.wraps_vertically() {
+            nr = nr.rem_euclid(self.height as i64);
+        }
+        if self.wraps_horizontally() {
+            nc = nc.rem_euclid(self.width as i64);
+        }
+
+        if nr < 0 || nc < 0 || nr >= self.height as i64 || nc >= self.width as i64 {
+            return None;
+        }
+        Some((nr as u32, nc as u32))
+    }
+
+    /// Like `neighbor`, but a disabled cell counts as outside the maze
+    pub fn enabled_neighbor(&self, row: u32, col: u32, dr: i32, dc: i32) -> Option<(u32, u32)> {
+        self.neighbor(row, col, dr, dc).filter(|&(nr, nc)| self.is_enabled(nr, nc))
+    }
+
+    /// The unit step (dr, dc) that leads from the first cell to the second,
+    /// if they are adjacent directly or across a seam.
+    pub fn direction(&self, row1: u32, col1: u32, row2: u32, col2: u32) -> Option<(i32, i32)> {
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .copied()
+            .find(|&(dr, dc)| self.neighbor(row1, col1, dr, dc) == Some((row2, col2)))
+    }
 }
 
 impl fmt::Display for Maze {
@@ -110,7 +451,7 @@ impl fmt::Display for Maze {
                 let symbol = if cell.get_type() == CellType::Default { '◻' } else { '◼' };
                 write!(f, "{}", symbol)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         Ok(())
@@ -125,3 +466,60 @@ pub struct MazeChange {
     pub old: Cell,
     pub new: Cell,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbours_wrap_across_the_seams() {
+        let mut maze = Maze::new(4, 3);
+        maze.set_wrap(true, false);
+        assert_eq!(maze.neighbor(1, 0, 0, -1), Some((1, 3)));
+        assert_eq!(maze.neighbor(0, 1, -1, 0), None);
+        assert_eq!(maze.direction(1, 3, 1, 0), Some((0, 1)));
+        assert!(maze.crosses_seam(1, 0, WALL_W) && !maze.crosses_seam(1, 1, WALL_W));
+        assert!(maze.passages(1, 0).contains(&(1, 3)));
+
+        maze.get_cell_mut(1, 0).add_wall(WALL_W);
+        assert!(!maze.crosses_seam(1, 0, WALL_W));
+
+        maze.set_wrap(true, true);
+        assert_eq!(maze.neighbor(0, 0, -1, 0), Some((2, 0)));
+        assert_eq!(maze.neighbor(2, 3, 1, 1), Some((0, 0)));
+
+        // Two cells wide, the seam would join cells that already touch
+        let mut narrow = Maze::new(2, 3);
+        narrow.set_wrap(true, true);
+        assert!(!narrow.wraps_horizontally() && narrow.wraps_vertically());
+        assert_eq!(narrow.neighbor(0, 0, 0, -1), None);
+    }
+
+    #[test]
+    fn masks_split_the_maze_into_regions() {
+        let mut maze = Maze::new(1, 1);
+        maze.set_mask_ascii("##.#\n## #\n#");
+        assert_eq!((maze.width(), maze.height()), (4, 3));
+        assert_eq!(maze.enabled_cells(), 7);
+        // A short line is disabled past its end
+        assert!(maze.is_enabled(2, 0) && !maze.is_enabled(2, 1));
+        assert_eq!(maze.region_size(0, 0), 5);
+        assert_eq!(maze.region_size(1, 3), 2);
+
+        assert_eq!(maze.enabled_neighbor(0, 1, 0, 1), None);
+        assert!(maze.faces_outside(0, 1, WALL_E) && !maze.faces_outside(0, 0, WALL_E));
+
+        let mut bits = Maze::new(3, 2);
+        bits.set_mask_bits(&[0b0010_1101]);
+        let enabled: Vec<bool> = (0..6).map(|i| bits.is_enabled(i / 3, i % 3)).collect();
+        assert_eq!(enabled, vec![true, false, true, true, false, true]);
+
+        let mut image = Maze::new(2, 2);
+        image.set_mask_image(&[0, 0, 255, 255, 0, 0, 255, 255, 255, 255, 0, 0, 255, 255, 0, 0], 4, 4);
+        assert_eq!(image.enabled_cells(), 2);
+        assert!(image.is_enabled(0, 0) && image.is_enabled(1, 1));
+
+        image.apply_mask(&[false; 4]);
+        assert_eq!(image.random_enabled_cell(), None);
+    }
+}
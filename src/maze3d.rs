@@ -0,0 +1,228 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{Cell, CellType, WALL_MASK},
+    grid::{GridCell, GridMaze, Link, Topology},
+    maze::Maze,
+    utils
+};
+
+// The first four sides line up with WALL_N, WALL_E, WALL_S and WALL_W so a
+// level can be copied straight into a `Maze`
+pub const SIDE_N: u8 = 0;
+pub const SIDE_E: u8 = 1;
+pub const SIDE_S: u8 = 2;
+pub const SIDE_W: u8 = 3;
+pub const SIDE_UP: u8 = 4;
+pub const SIDE_DOWN: u8 = 5;
+
+/// A stack of square levels of the same size. An open `SIDE_UP` wall is a
+/// staircase to the cell right above, which has `SIDE_DOWN` open in turn.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Maze3D {
+    width: u32,
+    height: u32,
+    levels: u32,
+    cells: Vec<GridCell>,
+}
+
+#[wasm_bindgen]
+impl Maze3D {
+    pub fn new(width: u32, height: u32, levels: u32) -> Maze3D {
+        utils::set_panic_hook();
+
+        Maze3D {
+            width,
+            height,
+            levels,
+            cells: vec![GridCell::new(CellType::Default); (width * height * levels) as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+
+    /// Pointer to the `u16` cell values, level by level and row by row
+    pub fn cells(&self) -> *const GridCell {
+        self.cells.as_ptr()
+    }
+
+    /// Pointer to the `u16` cell values of one level
+    pub fn level_cells(&self, level: u32) -> *const GridCell {
+        self.cells[self.get_index(level, 0, 0)..].as_ptr()
+    }
+
+    /// A copy of one level as a regular `Maze`, without the stairs
+    pub fn level(&self, level: u32) -> Maze {
+        let mut maze = Maze::new(self.width, self.height);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let grid_cell = self.get_cell(level, row, col);
+                let mut cell = Cell::new(grid_cell.get_type());
+                cell.add_wall(grid_cell.walls() as u8 & WALL_MASK);
+                maze.set_cell(row, col, cell);
+            }
+        }
+        maze
+    }
+
+    pub fn has_stairs_up(&self, level: u32, row: u32, col: u32) -> bool {
+        level + 1 < self.levels && !self.get_cell(level, row, col).has_wall(SIDE_UP)
+    }
+
+    pub fn has_stairs_down(&self, level: u32, row: u32, col: u32) -> bool {
+        level > 0 && !self.get_cell(level, row, col).has_wall(SIDE_DOWN)
+    }
+
+    /// Stair markers for one level as `[row, col, direction, ...]`, where the
+    /// direction is 0 for stairs going up and 1 for stairs going down.
+    pub fn stairs(&self, level: u32) -> Vec<u32> {
+        let mut stairs = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.has_stairs_up(level, row, col) {
+                    stairs.extend_from_slice(&[row, col, 0]);
+                }
+                if self.has_stairs_down(level, row, col) {
+                    stairs.extend_from_slice(&[row, col, 1]);
+                }
+            }
+        }
+        stairs
+    }
+
+    fn get_index(&self, level: u32, row: u32, col: u32) -> usize {
+        assert!(level < self.levels, "Level out of bounds: {} >= {}", level, self.levels);
+        assert!(row < self.height, "Row out of bounds: {} >= {}", row, self.height);
+        assert!(col < self.width, "Column out of bounds: {} >= {}", col, self.width);
+        ((level * self.height + row) * self.width + col) as usize
+    }
+}
+
+impl Maze3D {
+    pub fn get_cell(&self, level: u32, row: u32, col: u32) -> GridCell {
+        self.cells[self.get_index(level, row, col)]
+    }
+
+    pub fn set_cell(&mut self, level: u32, row: u32, col: u32, cell: GridCell) {
+        let idx = self.get_index(level, row, col);
+        self.cells[idx] = cell;
+    }
+
+    /// Level, row and column of the cell at `index`
+    pub fn position(&self, index: usize) -> (u32, u32, u32) {
+        let index = index as u32;
+        let per_level = self.width * self.height;
+        (index / per_level, index % per_level / self.width, index % self.width)
+    }
+}
+
+impl Topology for Maze3D {
+    fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn side_count(&self, _index: usize) -> u8 {
+        6
+    }
+
+    fn links(&self, index: usize) -> Vec<Link> {
+        let (level, row, col) = self.position(index);
+        let mut links = Vec::new();
+
+        let mut push = |side, back, level: u32, row: u32, col: u32| {
+            links.push(Link { side, neighbor: self.get_index(level, row, col), back });
+        };
+
+        if row > 0 {
+            push(SIDE_N, SIDE_S, level, row - 1, col);
+        }
+        if col + 1 < self.width {
+            push(SIDE_E, SIDE_W, level, row, col + 1);
+        }
+        if row + 1 < self.height {
+            push(SIDE_S, SIDE_N, level, row + 1, col);
+        }
+        if col > 0 {
+            push(SIDE_W, SIDE_E, level, row, col - 1);
+        }
+        if level + 1 < self.levels {
+            push(SIDE_UP, SIDE_DOWN, level + 1, row, col);
+        }
+        if level > 0 {
+            push(SIDE_DOWN, SIDE_UP, level - 1, row, col);
+        }
+
+        links
+    }
+}
+
+impl GridMaze for Maze3D {
+    fn grid_cells(&self) -> &[GridCell] {
+        &self.cells
+    }
+
+    fn set_grid_cell(&mut self, index: usize, cell: GridCell) {
+        self.cells[index] = cell;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cell::{WALL_E, WALL_N, WALL_W},
+        generators::assert_generates_spanning_trees,
+        grid::assert_links_match
+    };
+
+    #[test]
+    fn links_levels_through_stairs() {
+        let maze = Maze3D::new(3, 2, 3);
+        assert_eq!(maze.links(0).len(), 3);
+        assert_eq!(maze.links(maze.get_index(1, 0, 1)).len(), 5);
+        assert_eq!(maze.position(maze.get_index(2, 1, 2)), (2, 1, 2));
+        assert_links_match(&maze);
+    }
+
+    #[test]
+    fn copies_levels_and_lists_stairs() {
+        let mut maze = Maze3D::new(2, 2, 2);
+        let mut closed = GridCell::new(CellType::Default);
+        closed.set_walls((1 << 6) - 1);
+        for index in 0..maze.cell_count() {
+            maze.set_grid_cell(index, closed);
+        }
+
+        let mut stairs = closed;
+        stairs.remove_wall(SIDE_S);
+        stairs.remove_wall(SIDE_DOWN);
+        // The top level has nowhere to go up to, whatever its walls say
+        stairs.remove_wall(SIDE_UP);
+        maze.set_cell(1, 0, 1, stairs);
+        let mut below = closed;
+        below.remove_wall(SIDE_UP);
+        maze.set_cell(0, 0, 1, below);
+
+        assert_eq!(maze.stairs(0), vec![0, 1, 0]);
+        assert_eq!(maze.stairs(1), vec![0, 1, 1]);
+
+        let level = maze.level(1);
+        assert_eq!(level.get_cell(0, 1).raw() & WALL_MASK, WALL_N | WALL_E | WALL_W);
+    }
+
+    #[test]
+    fn every_generator_carves_a_spanning_tree() {
+        assert_generates_spanning_trees(&Maze3D::new(4, 3, 3));
+    }
+}
@@ -0,0 +1,198 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    maze::{Maze, Opening},
+    utils::random
+};
+
+/// Where `Maze::place_start_end` puts the start and end
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The two ends of the longest shortest path. Exact for perfect mazes,
+    /// a close estimate when there are loops.
+    Diameter,
+    /// The two boundary cells furthest apart, opened as the entrance and
+    /// exit
+    BoundaryDiameter,
+    /// Two random connected boundary cells, opened as the entrance and exit
+    RandomBoundary,
+    /// The first and last enabled cell, top left and bottom right in an
+    /// unmasked maze
+    OppositeCorners,
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Mark a `Start` and an `End` cell using `strategy`, replacing any
+    /// there were along with the entrance and exit. Returns
+    /// `[start_row, start_col, end_row, end_col]`, or nothing if the maze
+    /// has no two connected cells to use.
+    pub fn place_start_end(&mut self, strategy: Placement) -> Vec<u32> {
+        let ends = match strategy {
+            Placement::Diameter => self.diameter(|_, _| true),
+            Placement::BoundaryDiameter => self.diameter(|maze, cell| maze.is_on_boundary(cell)),
+            Placement::RandomBoundary => self.random_boundary_pair(),
+            Placement::OppositeCorners => self.opposite_corners(),
+        };
+        let (start, end) = match ends {
+            Some(ends) => ends,
+            None => return Vec::new(),
+        };
+
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if matches!(self.get_cell(row, col).get_type(), CellType::Start | CellType::End) {
+                    self.get_cell_mut(row, col).set_type(CellType::Default);
+                }
+            }
+        }
+        self.get_cell_mut(start.0, start.1).set_type(CellType::Start);
+        self.get_cell_mut(end.0, end.1).set_type(CellType::End);
+
+        self.clear_opening(Opening::Entrance);
+        self.clear_opening(Opening::Exit);
+        if matches!(strategy, Placement::BoundaryDiameter | Placement::RandomBoundary) {
+            self.open_boundary(Opening::Entrance, start, &[WALL_N, WALL_W, WALL_S, WALL_E]);
+            self.open_boundary(Opening::Exit, end, &[WALL_S, WALL_E, WALL_N, WALL_W]);
+        }
+
+        vec![start.0, start.1, end.0, end.1]
+    }
+}
+
+impl Maze {
+    fn cells_where(&self, keep: impl Fn(&Maze, (u32, u32)) -> bool) -> Vec<(u32, u32)> {
+        (0..self.height())
+            .flat_map(|row| (0..self.width()).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.is_enabled(row, col) && keep(self, (row, col)))
+            .collect()
+    }
+
+    /// Whether the cell has a side on the outside of the maze
+    fn is_on_boundary(&self, (row, col): (u32, u32)) -> bool {
+        [WALL_N, WALL_E, WALL_S, WALL_W].iter().any(|&wall| self.faces_outside(row, col, wall))
+    }
+
+    /// The candidate furthest from `from` and its distance
+    fn farthest_of(&self, from: (u32, u32), candidates: &[(u32, u32)]) -> Option<((u32, u32), u32)> {
+        let distances = self.distances_from(from.0, from.1);
+        candidates
+            .iter()
+            .filter_map(|&(row, col)| {
+                distances[(row * self.width() + col) as usize].map(|distance| ((row, col), distance))
+            })
+            .max_by_key(|&(_, distance)| distance)
+    }
+
+    /// Double sweep inside the component holding the most candidates: the
+    /// candidate furthest from any of them, then the one furthest from that
+    fn diameter(&self, keep: impl Fn(&Maze, (u32, u32)) -> bool) -> Option<((u32, u32), (u32, u32))> {
+        let candidates = self.cells_where(keep);
+        let index = |(row, col): (u32, u32)| (row * self.width() + col) as usize;
+
+        // One sweep per component, each marking the candidates it reaches
+        let mut seen = vec![false; (self.width() * self.height()) as usize];
+        let mut largest: Option<((u32, u32), usize)> = None;
+        for &cell in &candidates {
+            if seen[index(cell)] {
+                continue;
+            }
+            let distances = self.distances_from(cell.0, cell.1);
+            let reached: Vec<((u32, u32), u32)> = candidates
+                .iter()
+                .filter_map(|&other| distances[index(other)].map(|distance| (other, distance)))
+                .collect();
+            for &(other, _) in &reached {
+                seen[index(other)] = true;
+            }
+
+            if largest.is_none_or(|(_, size)| reached.len() > size) {
+                let (farthest, _) = *reached.iter().max_by_key(|&&(_, distance)| distance)?;
+                largest = Some((farthest, reached.len()));
+            }
+        }
+
+        let (start, _) = largest?;
+        match self.farthest_of(start, &candidates)? {
+            (end, distance) if distance > 0 => Some((start, end)),
+            _ => None,
+        }
+    }
+
+    fn random_boundary_pair(&self) -> Option<((u32, u32), (u32, u32))> {
+        let boundary = self.cells_where(|maze, cell| maze.is_on_boundary(cell));
+        let pick = |cells: &[(u32, u32)]| cells[(random() * cells.len() as f64) as usize % cells.len()];
+        if boundary.is_empty() {
+            return None;
+        }
+
+        let start = pick(&boundary);
+        let distances = self.distances_from(start.0, start.1);
+        let reachable: Vec<(u32, u32)> = boundary
+            .into_iter()
+            .filter(|&(row, col)| (row, col) != start && distances[(row * self.width() + col) as usize].is_some())
+            .collect();
+        if reachable.is_empty() {
+            return None;
+        }
+        Some((start, pick(&reachable)))
+    }
+
+    fn opposite_corners(&self) -> Option<((u32, u32), (u32, u32))> {
+        let cells = self.cells_where(|_, _| true);
+        match (cells.first(), cells.last()) {
+            (Some(&first), Some(&last)) if first != last => Some((first, last)),
+            _ => None,
+        }
+    }
+
+    /// Open the first wall in `preferred` that faces the outside
+    fn open_boundary(&mut self, opening: Opening, (row, col): (u32, u32), preferred: &[u8]) {
+        if let Some(&wall) = preferred.iter().find(|&&wall| self.faces_outside(row, col, wall)) {
+            self.set_opening(opening, row, col, wall);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::MazeBuilder;
+
+    #[test]
+    fn places_ends_of_the_longest_path() {
+        let (_, mut maze) = MazeBuilder::generated("kruskals", 4, &Maze::new(7, 7));
+
+        let ends = maze.place_start_end(Placement::Diameter);
+        let from_start = maze.distance_map(ends[0], ends[1]);
+        assert_eq!(from_start.distance(ends[2], ends[3]), Some(from_start.max_distance()));
+        assert_eq!(maze.get_cell(ends[0], ends[1]).get_type(), CellType::Start);
+
+        let ends = maze.place_start_end(Placement::BoundaryDiameter);
+        assert_eq!(maze.get_cell(ends[2], ends[3]).get_type(), CellType::End);
+        assert_eq!(maze.opening(Opening::Exit)[..2], ends[2..]);
+        assert!(maze.validate().is_consistent());
+
+        maze.place_start_end(Placement::OppositeCorners);
+        assert!(maze.opening(Opening::Entrance).is_empty());
+        assert!(maze.validate().is_perfect());
+    }
+
+    #[test]
+    fn sweeps_the_largest_region_of_a_split_mask() {
+        // A lone cell in the top left corner comes first in scan order
+        let mut maze = Maze::new(6, 4);
+        maze.set_mask_ascii("# ####\n  ####\n######\n######");
+        let (_, mut maze) = MazeBuilder::generated("prims", 2, &maze);
+
+        let ends = maze.place_start_end(Placement::Diameter);
+        assert_eq!(ends.len(), 4);
+        let from_start = maze.distance_map(ends[0], ends[1]);
+        assert_eq!(from_start.distance(ends[2], ends[3]), Some(from_start.max_distance()));
+        assert!(from_start.max_distance() >= 7);
+
+        assert_eq!(maze.place_start_end(Placement::BoundaryDiameter).len(), 4);
+    }
+}
@@ -0,0 +1,241 @@
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    grid::{GridCell, GridMaze, Link, Topology},
+    utils
+};
+
+// Sides of a ring cell. The center cell has no inward or sideways walls,
+// so its outward sides start at 0 instead.
+pub const POLAR_IN: u8 = 0;
+pub const POLAR_CW: u8 = 1;
+pub const POLAR_CCW: u8 = 2;
+pub const POLAR_OUT: u8 = 3;
+
+/// A circular maze made of concentric rings around a single center cell.
+///
+/// Each ring is split into as many cells as keeps them roughly square, which
+/// is always a multiple of the ring inside it. A cell whose ring is followed
+/// by a ring with twice as many cells therefore has two outward neighbours.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct PolarMaze {
+    ring_counts: Vec<u32>,
+    ring_offsets: Vec<u32>,
+    cells: Vec<GridCell>,
+}
+
+#[wasm_bindgen]
+impl PolarMaze {
+    pub fn new(rings: u32) -> PolarMaze {
+        utils::set_panic_hook();
+
+        let mut ring_counts = Vec::with_capacity(rings as usize);
+        let mut ring_offsets = Vec::with_capacity(rings as usize);
+        let mut total = 0;
+
+        for ring in 0..rings {
+            let count = if ring == 0 {
+                1
+            } else {
+                // Split the ring so a cell is about as wide as it is tall
+                let previous = ring_counts[ring as usize - 1];
+                let circumference = 2.0 * PI * ring as f64;
+                let ratio = (circumference / previous as f64).round().max(1.0) as u32;
+                previous * ratio
+            };
+
+            ring_counts.push(count);
+            ring_offsets.push(total);
+            total += count;
+        }
+
+        PolarMaze {
+            ring_counts,
+            ring_offsets,
+            cells: vec![GridCell::new(CellType::Default); total as usize],
+        }
+    }
+
+    pub fn rings(&self) -> u32 {
+        self.ring_counts.len() as u32
+    }
+
+    /// Number of cells in `ring`
+    pub fn ring_cells(&self, ring: u32) -> u32 {
+        self.ring_counts[ring as usize]
+    }
+
+    /// Index into `cells` of the cell at `col` in `ring`
+    pub fn index(&self, ring: u32, col: u32) -> usize {
+        assert!(ring < self.rings(), "Ring out of bounds: {} >= {}", ring, self.rings());
+        assert!(
+            col < self.ring_cells(ring),
+            "Column out of bounds: {} >= {}", col, self.ring_cells(ring)
+        );
+        (self.ring_offsets[ring as usize] + col) as usize
+    }
+
+    /// Pointer to the `u16` cell values, ring by ring starting at the center
+    pub fn cells(&self) -> *const GridCell {
+        self.cells.as_ptr()
+    }
+
+    pub fn total_cells(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Arc of a cell for rings that are `size` pixels thick, as
+    /// `[inner radius, outer radius, start angle, end angle]`. Angles are in
+    /// radians and grow clockwise on a canvas, measured around the maze center.
+    pub fn cell_arc(&self, ring: u32, col: u32, size: f64) -> Vec<f64> {
+        let theta = 2.0 * PI / self.ring_cells(ring) as f64;
+        vec![
+            ring as f64 * size,
+            (ring + 1) as f64 * size,
+            col as f64 * theta,
+            (col + 1) as f64 * theta,
+        ]
+    }
+
+    /// Width and height of the canvas needed to draw the maze
+    pub fn pixel_size(&self, size: f64) -> f64 {
+        2.0 * self.rings() as f64 * size
+    }
+}
+
+impl PolarMaze {
+    pub fn get_cell(&self, ring: u32, col: u32) -> GridCell {
+        self.cells[self.index(ring, col)]
+    }
+
+    pub fn set_cell(&mut self, ring: u32, col: u32, cell: GridCell) {
+        let idx = self.index(ring, col);
+        self.cells[idx] = cell;
+    }
+
+    /// Ring and column of the cell at `index`
+    pub fn position(&self, index: usize) -> (u32, u32) {
+        let ring = self.ring_offsets.iter().rposition(|&offset| offset as usize <= index).unwrap();
+        (ring as u32, index as u32 - self.ring_offsets[ring])
+    }
+
+    /// How many cells of the next ring border each cell of `ring`
+    pub fn outward_ratio(&self, ring: u32) -> u32 {
+        match self.ring_counts.get(ring as usize + 1) {
+            Some(next) => next / self.ring_cells(ring),
+            None => 0,
+        }
+    }
+
+    /// Side number of the `k`th outward wall of a cell in `ring`
+    pub fn outward_side(&self, ring: u32, k: u32) -> u8 {
+        if ring == 0 { k as u8 } else { POLAR_OUT + k as u8 }
+    }
+}
+
+impl Topology for PolarMaze {
+    fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn side_count(&self, index: usize) -> u8 {
+        let (ring, _) = self.position(index);
+        // The outermost ring still has one wall on the outside
+        let outward = self.outward_ratio(ring).max(1) as u8;
+        if ring == 0 { outward } else { POLAR_OUT + outward }
+    }
+
+    fn links(&self, index: usize) -> Vec<Link> {
+        let (ring, col) = self.position(index);
+        let mut links = Vec::new();
+
+        if ring > 0 {
+            let count = self.ring_cells(ring);
+            let ratio = self.outward_ratio(ring - 1);
+            links.push(Link {
+                side: POLAR_IN,
+                neighbor: self.index(ring - 1, col / ratio),
+                back: self.outward_side(ring - 1, col % ratio),
+            });
+            links.push(Link {
+                side: POLAR_CW,
+                neighbor: self.index(ring, (col + 1) % count),
+                back: POLAR_CCW,
+            });
+            links.push(Link {
+                side: POLAR_CCW,
+                neighbor: self.index(ring, (col + count - 1) % count),
+                back: POLAR_CW,
+            });
+        }
+
+        let ratio = self.outward_ratio(ring);
+        for k in 0..ratio {
+            links.push(Link {
+                side: self.outward_side(ring, k),
+                neighbor: self.index(ring + 1, col * ratio + k),
+                back: POLAR_IN,
+            });
+        }
+
+        links
+    }
+}
+
+impl GridMaze for PolarMaze {
+    fn grid_cells(&self) -> &[GridCell] {
+        &self.cells
+    }
+
+    fn set_grid_cell(&mut self, index: usize, cell: GridCell) {
+        self.cells[index] = cell;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generators::assert_generates_spanning_trees, grid::assert_links_match};
+
+    #[test]
+    fn splits_rings_and_links_them() {
+        let maze = PolarMaze::new(5);
+        let counts: Vec<u32> = (0..maze.rings()).map(|ring| maze.ring_cells(ring)).collect();
+        assert_eq!(counts, vec![1, 6, 12, 24, 24]);
+
+        // The center opens onto the whole first ring, a cell before a
+        // doubling ring has two outward neighbours, the last ring none
+        assert_eq!(maze.links(0).len(), 6);
+        assert_eq!(maze.links(maze.index(1, 0)).len(), 5);
+        assert_eq!(maze.links(maze.index(4, 0)).len(), 3);
+        assert_eq!(maze.side_count(maze.index(4, 0)), 4);
+
+        for index in 0..maze.total_cells() {
+            let (ring, col) = maze.position(index);
+            assert_eq!(maze.index(ring, col), index);
+        }
+        assert_links_match(&maze);
+    }
+
+    #[test]
+    fn outward_cells_sit_inside_their_parent_arc() {
+        let maze = PolarMaze::new(4);
+        let outward: Vec<Link> = maze.links(maze.index(2, 5)).into_iter().filter(|link| link.back == POLAR_IN).collect();
+        assert_eq!(outward.len(), 2);
+        for link in outward {
+            let (ring, col) = maze.position(link.neighbor);
+            let (parent, child) = (maze.cell_arc(2, 5, 1.0), maze.cell_arc(ring, col, 1.0));
+            assert_eq!(child[0], parent[1]);
+            assert!(child[2] >= parent[2] - 1e-9 && child[3] <= parent[3] + 1e-9, "{:?} {:?}", parent, child);
+        }
+    }
+
+    #[test]
+    fn every_generator_carves_a_spanning_tree() {
+        assert_generates_spanning_trees(&PolarMaze::new(6));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    generators::MazeBuilder,
+    maze::Maze,
+    render::{
+        png::{image_data, image_header, write_chunk, PNG_SIGNATURE},
+        raster::{rasterize, Canvas}
+    }
+};
+
+/// Frame timing and size for `MazeBuilder::to_gif` and `to_apng`
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct AnimationOptions {
+    scale: u32,
+    frame_delay: u32,
+    steps_per_frame: usize,
+    end_delay: u32,
+}
+
+#[wasm_bindgen]
+impl AnimationOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AnimationOptions {
+        AnimationOptions {
+            scale: 10,
+            frame_delay: 50,
+            steps_per_frame: 1,
+            end_delay: 2000,
+        }
+    }
+
+    /// Pixels per cell
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale;
+    }
+
+    /// Milliseconds each frame is shown. GIF rounds this to hundredths.
+    pub fn set_frame_delay(&mut self, delay: u32) {
+        self.frame_delay = delay;
+    }
+
+    /// How many recorded steps one frame covers
+    pub fn set_steps_per_frame(&mut self, steps: usize) {
+        self.steps_per_frame = steps.max(1);
+    }
+
+    /// Milliseconds the finished maze is held before the animation loops
+    pub fn set_end_delay(&mut self, delay: u32) {
+        self.end_delay = delay;
+    }
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions::new()
+    }
+}
+
+#[wasm_bindgen]
+impl MazeBuilder {
+    /// Animate the recorded steps, starting from `maze`, as a looping GIF
+    pub fn to_gif(&self, maze: &Maze, options: &AnimationOptions) -> Vec<u8> {
+        encode_gif(&self.frames(maze, options))
+    }
+
+    /// Animate the recorded steps, starting from `maze`, as a looping APNG
+    pub fn to_apng(&self, maze: &Maze, options: &AnimationOptions) -> Vec<u8> {
+        encode_apng(&self.frames(maze, options))
+    }
+}
+
+impl MazeBuilder {
+    /// One canvas per `steps_per_frame` steps with the cells they changed
+    /// outlined, then the finished maze without outlines. Delays are in
+    /// milliseconds.
+    fn frames(&self, maze: &Maze, options: &AnimationOptions) -> Vec<(Canvas, u32)> {
+        let mut maze = maze.clone();
+        maze.clear_under_crossings();
+        let mut frames = Vec::new();
+
+        let steps: Vec<usize> = (0..self.total_steps()).collect();
+        for batch in steps.chunks(options.steps_per_frame) {
+            let mut highlight = Vec::new();
+            for &step in batch {
+                self.apply_step(&mut maze, step);
+                highlight.extend(self.step_changes(step).iter().map(|change| (change.row, change.col)));
+            }
+            frames.push((rasterize(&maze, options.scale, &highlight), options.frame_delay));
+        }
+        frames.push((rasterize(&maze, options.scale, &[]), options.end_delay));
+
+        frames
+    }
+}
+
+fn encode_apng(frames: &[(Canvas, u32)]) -> Vec<u8> {
+    let first = &frames[0].0;
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &image_header(first));
+
+    // Frame count, then 0 plays for looping forever
+    let mut control = (frames.len() as u32).to_be_bytes().to_vec();
+    control.extend_from_slice(&0u32.to_be_bytes());
+    write_chunk(&mut png, b"acTL", &control);
+
+    // fcTL and fdAT chunks share one sequence
+    let mut sequence = 0u32;
+    for (i, (canvas, delay)) in frames.iter().enumerate() {
+        let mut frame = sequence.to_be_bytes().to_vec();
+        frame.extend_from_slice(&canvas.width.to_be_bytes());
+        frame.extend_from_slice(&canvas.height.to_be_bytes());
+        frame.extend_from_slice(&[0; 8]); // x and y offset
+        frame.extend_from_slice(&((*delay).min(u16::MAX as u32) as u16).to_be_bytes());
+        frame.extend_from_slice(&1000u16.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]); // no disposal, no blending
+        write_chunk(&mut png, b"fcTL", &frame);
+        sequence += 1;
+
+        // The first frame doubles as the still image
+        if i == 0 {
+            write_chunk(&mut png, b"IDAT", &image_data(canvas));
+        } else {
+            let mut data = sequence.to_be_bytes().to_vec();
+            data.extend(image_data(canvas));
+            write_chunk(&mut png, b"fdAT", &data);
+            sequence += 1;
+        }
+    }
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn encode_gif(frames: &[(Canvas, u32)]) -> Vec<u8> {
+    // Mazes only use a handful of colours, so every frame shares one exact
+    // palette. Past 256 colours the extras fall back to the first entry.
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut lookup: HashMap<[u8; 3], u8> = HashMap::new();
+    let indexed: Vec<Vec<u8>> = frames
+        .iter()
+        .map(|(canvas, _)| {
+            canvas
+                .pixels
+                .chunks_exact(3)
+                .map(|pixel| {
+                    let color = [pixel[0], pixel[1], pixel[2]];
+                    *lookup.entry(color).or_insert_with(|| {
+                        if palette.len() == 256 {
+                            return 0;
+                        }
+                        palette.push(color);
+                        (palette.len() - 1) as u8
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    // The colour table holds a power of two entries, at least 4 so the
+    // LZW minimum code size is valid
+    let mut bits = 2;
+    while (1 << bits) < palette.len() {
+        bits += 1;
+    }
+    palette.resize(1 << bits, [0, 0, 0]);
+
+    let (width, height) = (frames[0].0.width as u16, frames[0].0.height as u16);
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    gif.extend_from_slice(&[0x80 | 0x70 | (bits - 1), 0, 0]);
+    for color in &palette {
+        gif.extend_from_slice(color);
+    }
+
+    // Loop forever
+    gif.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for ((_, delay), indices) in frames.iter().zip(&indexed) {
+        let centiseconds = (delay / 10).min(u16::MAX as u32) as u16;
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+        gif.extend_from_slice(&centiseconds.to_le_bytes());
+        gif.extend_from_slice(&[0x00, 0x00]);
+
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0);
+
+        gif.push(bits);
+        for block in lzw_encode(indices, bits).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3B);
+    gif
+}
+
+/// GIF flavoured LZW: variable width codes up to 12 bits, packed from the
+/// lowest bit, with a clear code whenever the table fills up
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = CodeWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut width = min_code_size + 1;
+    let mut next = end + 1;
+    writer.push(clear, width);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let current = match prefix {
+            None => {
+                prefix = Some(index as u16);
+                continue;
+            }
+            Some(current) => current,
+        };
+
+        if let Some(&code) = table.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        writer.push(current, width);
+        // The decoder adds table entries one code behind us, so only widen
+        // once it will have caught up to a full table
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+        if next < 4096 {
+            table.insert((current, index), next);
+            next += 1;
+        } else {
+            writer.push(clear, width);
+            table.clear();
+            width = min_code_size + 1;
+            next = end + 1;
+        }
+        prefix = Some(index as u16);
+    }
+
+    if let Some(current) = prefix {
+        writer.push(current, width);
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+    }
+    writer.push(end, width);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct CodeWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl CodeWriter {
+    fn push(&mut self, code: u16, width: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_frame_per_batch_plus_the_result() {
+        let maze = Maze::new(5, 5);
+        let mut builder = MazeBuilder::with_generator("kruskals");
+        builder.set_seed(Some(7));
+        builder.generate_all(&maze);
+
+        let mut options = AnimationOptions::new();
+        options.set_steps_per_frame(4);
+        let expected = builder.total_steps().div_ceil(4) as u32 + 1;
+
+        let apng = builder.to_apng(&maze, &options);
+        assert_eq!(&apng[37..41], b"acTL");
+        assert_eq!(u32::from_be_bytes([apng[41], apng[42], apng[43], apng[44]]), expected);
+
+        let gif = builder.to_gif(&maze, &options);
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif.last(), Some(&0x3B));
+    }
+}
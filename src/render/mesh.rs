@@ -0,0 +1,264 @@
+use std::{collections::HashMap, fmt::Write};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    formats::BLOCK_WALL,
+    maze::Maze
+};
+
+/// Sizes for `Maze::to_obj` and `to_stl`, in millimetres
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct MeshOptions {
+    cell_size: f64,
+    wall_thickness: f64,
+    wall_height: f64,
+    floor_thickness: f64,
+}
+
+#[wasm_bindgen]
+impl MeshOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MeshOptions {
+        MeshOptions {
+            cell_size: 10.0,
+            wall_thickness: 1.5,
+            wall_height: 8.0,
+            floor_thickness: 2.0,
+        }
+    }
+
+    /// Distance between the centers of neighbouring walls
+    pub fn set_cell_size(&mut self, size: f64) {
+        self.cell_size = size;
+    }
+
+    /// Clamped to stay thinner than a cell
+    pub fn set_wall_thickness(&mut self, thickness: f64) {
+        self.wall_thickness = thickness;
+    }
+
+    pub fn set_wall_height(&mut self, height: f64) {
+        self.wall_height = height;
+    }
+
+    pub fn set_floor_thickness(&mut self, thickness: f64) {
+        self.floor_thickness = thickness;
+    }
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        MeshOptions::new()
+    }
+}
+
+/// A triangle mesh with shared vertices, wound counter-clockwise seen from
+/// outside
+pub struct Mesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// The walls extruded on a floor slab, as a Wavefront OBJ
+    pub fn to_obj(&self, options: &MeshOptions) -> String {
+        let mesh = self.to_mesh(options);
+        let mut obj = String::from("# mazeweb\no maze\n");
+        for [x, y, z] in &mesh.vertices {
+            writeln!(obj, "v {} {} {}", x, y, z).unwrap();
+        }
+        for [a, b, c] in &mesh.triangles {
+            // OBJ counts vertices from 1
+            writeln!(obj, "f {} {} {}", a + 1, b + 1, c + 1).unwrap();
+        }
+        obj
+    }
+
+    /// The walls extruded on a floor slab, as a binary STL
+    pub fn to_stl(&self, options: &MeshOptions) -> Vec<u8> {
+        let mesh = self.to_mesh(options);
+        let mut stl = vec![0; 80];
+        stl[..7].copy_from_slice(b"mazeweb");
+        stl.extend_from_slice(&(mesh.triangles.len() as u32).to_le_bytes());
+
+        for triangle in &mesh.triangles {
+            let [a, b, c] = triangle.map(|i| mesh.vertices[i as usize]);
+            let normal = normalize(cross(sub(b, a), sub(c, a)));
+            for point in [normal, a, b, c] {
+                for value in point {
+                    stl.extend_from_slice(&(value as f32).to_le_bytes());
+                }
+            }
+            stl.extend_from_slice(&[0, 0]);
+        }
+
+        stl
+    }
+}
+
+impl Maze {
+    /// Build the mesh as a height field over the block maze: wall blocks
+    /// rise to the wall height, the rest stay at floor level. Every face is
+    /// split at the same breakpoints, so the surface is closed and
+    /// manifold.
+    pub fn to_mesh(&self, options: &MeshOptions) -> Mesh {
+        let blocks = self.to_blocks();
+        let cell_size = options.cell_size.max(f64::EPSILON);
+        let thickness = options.wall_thickness.clamp(f64::EPSILON, cell_size * 0.99);
+
+        // Even blocks are wall strips, odd ones the space inside a cell
+        let breakpoints = |count: u32| {
+            let mut points = vec![0.0];
+            for i in 0..count {
+                let size = if i % 2 == 0 { thickness } else { cell_size - thickness };
+                points.push(points[i as usize] + size);
+            }
+            points
+        };
+        let xs = breakpoints(blocks.width());
+        let ys = breakpoints(blocks.height());
+        // Rows run down the plan, y runs up
+        let total_y = *ys.last().unwrap();
+        let levels = [-options.floor_thickness, 0.0, options.wall_height];
+
+        // Heights are level indices, -1 is the outside
+        let height = |row: i64, col: i64| -> i64 {
+            if row < 0 || col < 0 || row >= blocks.height() as i64 || col >= blocks.width() as i64 {
+                return -1;
+            }
+            if blocks.get_cell(row as u32, col as u32).get_type() == BLOCK_WALL { 2 } else { 1 }
+        };
+
+        let mut builder = MeshBuilder::default();
+        let vertex = |x: usize, y: usize, level: usize| {
+            (
+                (x, y, level),
+                [xs[x], total_y - ys[y], levels[level]],
+            )
+        };
+
+        for row in 0..blocks.height() as usize {
+            for col in 0..blocks.width() as usize {
+                let here = height(row as i64, col as i64) as usize;
+                let (x0, x1, y0, y1) = (col, col + 1, row, row + 1);
+
+                let top = [vertex(x0, y0, here), vertex(x1, y0, here), vertex(x1, y1, here), vertex(x0, y1, here)];
+                builder.quad(top, [0.0, 0.0, 1.0]);
+                let bottom = [vertex(x0, y0, 0), vertex(x1, y0, 0), vertex(x1, y1, 0), vertex(x0, y1, 0)];
+                builder.quad(bottom, [0.0, 0.0, -1.0]);
+
+                // Sides where the neighbour is lower or outside the slab,
+                // one quad per level band
+                let sides = [
+                    (-1, 0, (x0, y0), (x1, y0), [0.0, 1.0, 0.0]),
+                    (1, 0, (x0, y1), (x1, y1), [0.0, -1.0, 0.0]),
+                    (0, -1, (x0, y0), (x0, y1), [-1.0, 0.0, 0.0]),
+                    (0, 1, (x1, y0), (x1, y1), [1.0, 0.0, 0.0]),
+                ];
+                for (dr, dc, (ax, ay), (bx, by), normal) in sides {
+                    let there = height(row as i64 + dr, col as i64 + dc);
+                    for level in there.max(0) as usize..here {
+                        let face = [
+                            vertex(ax, ay, level),
+                            vertex(bx, by, level),
+                            vertex(bx, by, level + 1),
+                            vertex(ax, ay, level + 1),
+                        ];
+                        builder.quad(face, normal);
+                    }
+                }
+            }
+        }
+
+        builder.mesh
+    }
+}
+
+type Key = (usize, usize, usize);
+
+struct MeshBuilder {
+    mesh: Mesh,
+    indices: HashMap<Key, u32>,
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        MeshBuilder {
+            mesh: Mesh { vertices: Vec::new(), triangles: Vec::new() },
+            indices: HashMap::new(),
+        }
+    }
+}
+
+impl MeshBuilder {
+    fn index(&mut self, (key, point): (Key, [f64; 3])) -> u32 {
+        let vertices = &mut self.mesh.vertices;
+        *self.indices.entry(key).or_insert_with(|| {
+            vertices.push(point);
+            (vertices.len() - 1) as u32
+        })
+    }
+
+    /// Add a quad as two triangles, wound so they face along `normal`
+    fn quad(&mut self, corners: [(Key, [f64; 3]); 4], normal: [f64; 3]) {
+        let points = corners.map(|(_, point)| point);
+        let mut indices = corners.map(|corner| self.index(corner));
+        if dot(cross(sub(points[1], points[0]), sub(points[2], points[0])), normal) < 0.0 {
+            indices.reverse();
+        }
+        self.mesh.triangles.push([indices[0], indices[1], indices[2]]);
+        self.mesh.triangles.push([indices[0], indices[2], indices[3]]);
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: [f64; 3]) -> [f64; 3] {
+    let length = dot(a, a).sqrt();
+    if length == 0.0 {
+        return a;
+    }
+    [a[0] / length, a[1] / length, a[2] / length]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    use crate::generators::MazeBuilder;
+
+    #[test]
+    fn mesh_is_watertight() {
+        let (_, maze) = MazeBuilder::generated("kruskals", 5, &Maze::new(6, 5));
+
+        // Closed and consistently wound: every directed edge appears once,
+        // and its reverse belongs to exactly one other triangle
+        let mesh = maze.to_mesh(&MeshOptions::new());
+        let mut edges = HashSet::new();
+        for &[a, b, c] in &mesh.triangles {
+            for edge in [(a, b), (b, c), (c, a)] {
+                assert!(edges.insert(edge), "edge {:?} used twice", edge);
+            }
+        }
+        for &(a, b) in &edges {
+            assert!(edges.contains(&(b, a)), "edge {:?} is open", (a, b));
+        }
+
+        let stl = maze.to_stl(&MeshOptions::new());
+        assert_eq!(stl.len(), 84 + 50 * mesh.triangles.len());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    cell::{CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    maze::Maze
+};
+
+mod animation;
+mod mesh;
+mod png;
+mod raster;
+mod svg;
+mod text;
+
+// Keep in sync with the constants at the top of `www/index.js`
+pub const WALL_COLOR: &str = "#5c2f18";
+pub const OUTLINE_COLOR: &str = "#b86134";
+pub const BACKGROUND_COLOR: &str = "#B9D4AA";
+
+/// Fill colour of a cell, the same mapping as `cellColor` in `www/index.js`
+pub fn cell_color(cell_type: CellType) -> &'static str {
+    match cell_type {
+        CellType::Default => "#dad7da",
+        CellType::Start => "#00609b",
+        CellType::End => "#847244",
+        CellType::Path => "#7c2a0b",
+        CellType::Visited => "#1d8dcc",
+        CellType::LookingAt => "#c49358",
+        CellType::Current => "#5A827E",
+        CellType::Changing => "#7c2a0b",
+        CellType::Disabled => "#111",
+    }
+}
+
+/// RGB value of a `#rgb` or `#rrggbb` colour. Anything else is black.
+pub fn parse_color(color: &str) -> [u8; 3] {
+    let hex = color.trim_start_matches('#');
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or(0);
+    match hex.len() {
+        3 if hex.is_ascii() => [digit(0) * 17, digit(1) * 17, digit(2) * 17],
+        6 if hex.is_ascii() => [
+            digit(0) << 4 | digit(1),
+            digit(2) << 4 | digit(3),
+            digit(4) << 4 | digit(5),
+        ],
+        _ => [0, 0, 0],
+    }
+}
+
+/// Share of a tunnel edge covered by the stub at each of its ends
+pub const TUNNEL_STUB: f64 = 0.25;
+
+/// The neighbour across the `wall` side of a cell and the matching wall
+/// on its side.
+fn across(maze: &Maze, row: u32, col: u32, wall: u8) -> Option<(u32, u32, u8)> {
+    let (back, dr, dc) = match wall {
+        WALL_N => (WALL_S, -1, 0),
+        WALL_S => (WALL_N, 1, 0),
+        WALL_W => (WALL_E, 0, -1),
+        WALL_E => (WALL_W, 0, 1),
+        _ => return None,
+    };
+    maze.neighbor(row, col, dr, dc).map(|(nr, nc)| (nr, nc, back))
+}
+
+/// Whether the edge on the `wall` side of a cell should be drawn.
+///
+/// A shared edge is drawn if either cell has its wall bit set, except for
+/// the entrances of a weave tunnel. Those stay open so the passage shows.
+pub fn edge_closed(maze: &Maze, row: u32, col: u32, wall: u8) -> bool {
+    let here = maze.get_cell(row, col).has_wall(wall);
+    match across(maze, row, col, wall) {
+        Some((nr, nc, back)) => {
+            (here || maze.get_cell(nr, nc).has_wall(back)) && !edge_tunnels(maze, row, col, wall)
+        }
+        None => here,
+    }
+}
+
+/// Whether a weave passage goes under a crossing through the edge on the
+/// `wall` side of a cell: the crossing keeps its wall, the cell leading
+/// into the tunnel doesn't. Renderers leave the edge open and draw a stub
+/// at each end, so the passage on top looks bridged over the gap.
+pub fn edge_tunnels(maze: &Maze, row: u32, col: u32, wall: u8) -> bool {
+    let (nr, nc, back) = match across(maze, row, col, wall) {
+        Some(neighbor) => neighbor,
+        None => return false,
+    };
+    let here = maze.get_cell(row, col).has_wall(wall);
+    let there = maze.get_cell(nr, nc).has_wall(back);
+
+    match (here, there) {
+        (true, false) => maze.is_under_crossing(row, col),
+        (false, true) => maze.is_under_crossing(nr, nc),
+        _ => false,
+    }
+}
+
+/// Whether the edge on horizontal grid line `y` above column `x` is
+/// closed. Line `y` runs along the top of row `y`, the last one along the
+/// bottom of the last row.
+pub fn horizontal_edge(maze: &Maze, y: u32, x: u32) -> bool {
+    horizontal_line(maze, y, x, edge_closed)
+}
+
+/// Whether the edge on vertical grid line `x` beside row `y` is closed
+pub fn vertical_edge(maze: &Maze, x: u32, y: u32) -> bool {
+    vertical_line(maze, x, y, edge_closed)
+}
+
+/// Whether a weave tunnel runs through the edge on horizontal grid line
+/// `y` above column `x`
+pub fn horizontal_tunnel(maze: &Maze, y: u32, x: u32) -> bool {
+    horizontal_line(maze, y, x, edge_tunnels)
+}
+
+/// Whether a weave tunnel runs through the edge on vertical grid line `x`
+/// beside row `y`
+pub fn vertical_tunnel(maze: &Maze, x: u32, y: u32) -> bool {
+    vertical_line(maze, x, y, edge_tunnels)
+}
+
+fn horizontal_line(maze: &Maze, y: u32, x: u32, test: fn(&Maze, u32, u32, u8) -> bool) -> bool {
+    if maze.height() == 0 {
+        return false;
+    }
+    if y < maze.height() {
+        test(maze, y, x, WALL_N)
+    } else {
+        test(maze, y - 1, x, WALL_S)
+    }
+}
+
+fn vertical_line(maze: &Maze, x: u32, y: u32, test: fn(&Maze, u32, u32, u8) -> bool) -> bool {
+    if maze.width() == 0 {
+        return false;
+    }
+    if x < maze.width() {
+        test(maze, y, x, WALL_W)
+    } else {
+        test(maze, y, x - 1, WALL_E)
+    }
+}
+
+/// The grid edges a weave passage tunnels through, as unit
+/// `(x1, y1, x2, y2)` segments in cell units like `wall_segments`.
+pub fn tunnel_edges(maze: &Maze) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height) = (maze.width(), maze.height());
+    let mut edges = Vec::new();
+
+    for y in 0..=height {
+        for x in 0..width {
+            if horizontal_tunnel(maze, y, x) {
+                edges.push((x, y, x + 1, y));
+            }
+        }
+    }
+    for x in 0..=width {
+        for y in 0..height {
+            if vertical_tunnel(maze, x, y) {
+                edges.push((x, y, x, y + 1));
+            }
+        }
+    }
+
+    edges
+}
+
+/// Runs of closed edges merged into straight segments, as
+/// `(x1, y1, x2, y2)` in cell units.
+pub fn wall_segments(maze: &Maze) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height) = (maze.width(), maze.height());
+    let mut segments = Vec::new();
+
+    for y in 0..=height {
+        let mut start = None;
+        for x in 0..=width {
+            let closed = x < width && horizontal_edge(maze, y, x);
+            match (closed, start) {
+                (true, None) => start = Some(x),
+                (false, Some(from)) => {
+                    segments.push((from, y, x, y));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for x in 0..=width {
+        let mut start = None;
+        for y in 0..=height {
+            let closed = y < height && vertical_edge(maze, x, y);
+            match (closed, start) {
+                (true, None) => start = Some(y),
+                (false, Some(from)) => {
+                    segments.push((x, from, x, y));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cell::Cell, render::{svg::SvgOptions, text::TextStyle}};
+
+    #[test]
+    fn draws_a_gap_where_a_passage_tunnels_under() {
+        // A passage runs down the middle column over the centre cell, the
+        // one across the middle row tunnels under it
+        let mut maze = Maze::new(3, 3);
+        for row in 0..3 {
+            for col in 0..3 {
+                let mut cell = Cell::new(CellType::Default);
+                cell.add_wall(WALL_N | WALL_E | WALL_S | WALL_W);
+                maze.set_cell(row, col, cell);
+            }
+        }
+        maze.get_cell_mut(0, 1).remove_wall(WALL_S);
+        maze.get_cell_mut(1, 1).remove_wall(WALL_N | WALL_S);
+        maze.get_cell_mut(2, 1).remove_wall(WALL_N);
+        maze.get_cell_mut(1, 0).remove_wall(WALL_E);
+        maze.get_cell_mut(1, 2).remove_wall(WALL_W);
+        maze.set_under_crossing(1, 1, true);
+
+        assert_eq!(tunnel_edges(&maze), vec![(1, 1, 1, 2), (2, 1, 2, 2)]);
+        assert_eq!(
+            maze.to_text(TextStyle::Ascii, false),
+            "+--+--+--+\n|  |  |  |\n+--+  +--+\n|  :  :  |\n+--+  +--+\n|  |  |  |\n+--+--+--+\n"
+        );
+        assert!(Maze::from_ascii(&maze.to_text(TextStyle::Ascii, false)).is_ok());
+
+        let mut options = SvgOptions::new();
+        options.set_margin(0.0);
+        options.set_cell_size(8.0);
+        let svg = maze.to_svg(&options);
+        assert!(svg.contains("M8 8V10M8 14V16M16 8V10M16 14V16"), "{}", svg);
+
+        // At 20 pixels per cell the stub on the left tunnel edge covers
+        // y 22..31 and the middle of the edge stays the cell colour
+        let canvas = raster::rasterize(&maze, 20, &[]);
+        let pixel = |x: usize, y: usize| {
+            let at = (y * canvas.width as usize + x) * 3;
+            [canvas.pixels[at], canvas.pixels[at + 1], canvas.pixels[at + 2]]
+        };
+        assert_eq!(pixel(24, 24), parse_color(WALL_COLOR));
+        assert_eq!(pixel(24, 34), parse_color(cell_color(CellType::Default)));
+    }
+}
@@ -0,0 +1,103 @@
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    formats::crc32,
+    generators::MazeBuilder,
+    maze::Maze,
+    render::raster::{rasterize, Canvas}
+};
+
+pub const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+#[wasm_bindgen]
+impl Maze {
+    /// Render the maze as PNG bytes with `scale` pixels per cell
+    pub fn to_png(&self, scale: u32) -> Vec<u8> {
+        encode_png(&rasterize(self, scale, &[]))
+    }
+}
+
+#[wasm_bindgen]
+impl MazeBuilder {
+    /// Render `maze` as it looks after `step`, with the cells that step
+    /// changed outlined. Doesn't move the builder.
+    pub fn step_to_png(&self, maze: &Maze, step: usize, scale: u32) -> Vec<u8> {
+        let highlight: Vec<(u32, u32)> = self
+            .step_changes(step)
+            .iter()
+            .map(|change| (change.row, change.col))
+            .collect();
+        encode_png(&rasterize(&self.maze_at(maze, step), scale, &highlight))
+    }
+}
+
+/// Encode an RGB canvas as a PNG file
+pub fn encode_png(canvas: &Canvas) -> Vec<u8> {
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &image_header(canvas));
+    write_chunk(&mut png, b"IDAT", &image_data(canvas));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// IHDR contents: 8-bit RGB, no interlacing
+pub fn image_header(canvas: &Canvas) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&canvas.width.to_be_bytes());
+    header.extend_from_slice(&canvas.height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    header
+}
+
+/// Compressed scanlines, each unfiltered
+pub fn image_data(canvas: &Canvas) -> Vec<u8> {
+    let stride = canvas.width as usize * 3;
+    let mut raw = Vec::with_capacity((stride + 1) * canvas.height as usize);
+    for line in canvas.pixels.chunks_exact(stride.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+    compress_to_vec_zlib(&raw, 6)
+}
+
+/// Append a chunk with its length and CRC
+pub fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+    use crate::{
+        cell::{Cell, CellType},
+        render::{cell_color, parse_color}
+    };
+
+    #[test]
+    fn encodes_cell_colours() {
+        let mut maze = Maze::new(2, 1);
+        maze.set_cell(0, 1, Cell::new(CellType::Start));
+        let png = maze.to_png(10);
+
+        assert_eq!(&png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        // 2 cells of 10 pixels plus a 2 pixel margin on each side
+        assert_eq!(&png[16..24], &[0, 0, 0, 24, 0, 0, 0, 14]);
+
+        let idat_length = u32::from_be_bytes([png[33], png[34], png[35], png[36]]) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let raw = decompress_to_vec_zlib(&png[41..41 + idat_length]).unwrap();
+        let stride = 24 * 3 + 1;
+        let pixel = |x: usize, y: usize| &raw[y * stride + 1 + x * 3..y * stride + 4 + x * 3];
+        assert_eq!(pixel(7, 7), parse_color(cell_color(CellType::Default)));
+        assert_eq!(pixel(17, 7), parse_color(cell_color(CellType::Start)));
+    }
+}
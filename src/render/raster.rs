@@ -0,0 +1,96 @@
+use crate::{
+    maze::Maze,
+    render::{
+        cell_color, parse_color, tunnel_edges, wall_segments, BACKGROUND_COLOR, OUTLINE_COLOR, TUNNEL_STUB,
+        WALL_COLOR
+    }
+};
+
+/// An RGB image, three bytes per pixel, row by row
+#[derive(Clone)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, color: [u8; 3]) -> Canvas {
+        let pixels = color.iter().copied().cycle().take(width as usize * height as usize * 3).collect();
+        Canvas { width, height, pixels }
+    }
+
+    /// Fill a rectangle, clipped to the canvas
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 3]) {
+        let x1 = x.clamp(0, self.width as i64) as usize;
+        let y1 = y.clamp(0, self.height as i64) as usize;
+        let x2 = (x + width).clamp(0, self.width as i64) as usize;
+        let y2 = (y + height).clamp(0, self.height as i64) as usize;
+
+        for row in y1..y2 {
+            let start = (row * self.width as usize + x1) * 3;
+            let end = (row * self.width as usize + x2) * 3;
+            for pixel in self.pixels[start..end].chunks_exact_mut(3) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Draw the maze with `scale` pixels per cell. Cells in `highlight` get an
+/// outline, the way the canvas marks the cells a step changed.
+pub fn rasterize(maze: &Maze, scale: u32, highlight: &[(u32, u32)]) -> Canvas {
+    let scale = scale.max(1) as i64;
+    let thickness = (scale / 5).max(1);
+    // Wide enough that the outer walls aren't cut in half
+    let margin = thickness;
+
+    let mut canvas = Canvas::new(
+        (maze.width() as i64 * scale + 2 * margin) as u32,
+        (maze.height() as i64 * scale + 2 * margin) as u32,
+        parse_color(BACKGROUND_COLOR),
+    );
+
+    for row in 0..maze.height() {
+        for col in 0..maze.width() {
+            let color = parse_color(cell_color(maze.get_cell(row, col).get_type()));
+            let (x, y) = (margin + col as i64 * scale, margin + row as i64 * scale);
+            canvas.fill_rect(x, y, scale, scale, color);
+        }
+    }
+
+    let outline = parse_color(OUTLINE_COLOR);
+    let border = (scale / 10).max(1);
+    for &(row, col) in highlight {
+        let (x, y) = (margin + col as i64 * scale, margin + row as i64 * scale);
+        canvas.fill_rect(x, y, scale, border, outline);
+        canvas.fill_rect(x, y + scale - border, scale, border, outline);
+        canvas.fill_rect(x, y, border, scale, outline);
+        canvas.fill_rect(x + scale - border, y, border, scale, outline);
+    }
+
+    // Walls are centered on the grid lines
+    let wall = parse_color(WALL_COLOR);
+    let offset = margin - thickness / 2;
+    for (x1, y1, x2, y2) in wall_segments(maze) {
+        let (x1, y1) = (offset + x1 as i64 * scale, offset + y1 as i64 * scale);
+        let (x2, y2) = (offset + x2 as i64 * scale, offset + y2 as i64 * scale);
+        canvas.fill_rect(x1, y1, x2 - x1 + thickness, y2 - y1 + thickness, wall);
+    }
+
+    // Tunnel edges get a stub at each end, leaving a gap in the middle
+    let stub = (TUNNEL_STUB * scale as f64).round() as i64;
+    for (x1, y1, x2, y2) in tunnel_edges(maze) {
+        let (x1, y1) = (offset + x1 as i64 * scale, offset + y1 as i64 * scale);
+        let (x2, y2) = (offset + x2 as i64 * scale, offset + y2 as i64 * scale);
+        if y1 == y2 {
+            canvas.fill_rect(x1, y1, stub + thickness, thickness, wall);
+            canvas.fill_rect(x2 - stub, y1, stub + thickness, thickness, wall);
+        } else {
+            canvas.fill_rect(x1, y1, thickness, stub + thickness, wall);
+            canvas.fill_rect(x1, y2 - stub, thickness, stub + thickness, wall);
+        }
+    }
+
+    canvas
+}
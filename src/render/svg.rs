@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    maze::Maze,
+    render::{cell_color, tunnel_edges, wall_segments, TUNNEL_STUB, WALL_COLOR},
+    utils::escape_xml
+};
+
+/// Styling for `Maze::to_svg`. Sizes are in SVG user units.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SvgOptions {
+    cell_size: f64,
+    wall_thickness: f64,
+    margin: f64,
+    wall_color: String,
+    // One colour per `CellType`, indexed by the type nibble
+    type_colors: Vec<String>,
+    solution: Vec<(u32, u32)>,
+    solution_color: String,
+    solution_thickness: f64,
+}
+
+#[wasm_bindgen]
+impl SvgOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SvgOptions {
+        let types = [
+            CellType::Default, CellType::Start, CellType::End,
+            CellType::Path, CellType::Visited, CellType::LookingAt,
+            CellType::Current, CellType::Changing, CellType::Disabled,
+        ];
+
+        SvgOptions {
+            cell_size: 20.0,
+            wall_thickness: 2.0,
+            margin: 10.0,
+            wall_color: WALL_COLOR.to_string(),
+            type_colors: types.iter().map(|&t| cell_color(t).to_string()).collect(),
+            solution: Vec::new(),
+            solution_color: "#d23c3c".to_string(),
+            solution_thickness: 4.0,
+        }
+    }
+
+    pub fn set_cell_size(&mut self, size: f64) {
+        self.cell_size = size;
+    }
+
+    pub fn set_wall_thickness(&mut self, thickness: f64) {
+        self.wall_thickness = thickness;
+    }
+
+    pub fn set_margin(&mut self, margin: f64) {
+        self.margin = margin;
+    }
+
+    pub fn set_wall_color(&mut self, color: String) {
+        self.wall_color = color;
+    }
+
+    /// Fill colour for cells of `cell_type`. An empty string leaves them
+    /// unfilled.
+    pub fn set_type_color(&mut self, cell_type: CellType, color: String) {
+        self.type_colors[(cell_type as u8 >> 4) as usize] = color;
+    }
+
+    /// Solution path drawn as a polyline through the cell centers, given
+    /// as `[row, col, row, col, ...]`
+    pub fn set_solution(&mut self, cells: Vec<u32>) {
+        self.solution = cells.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+    }
+
+    pub fn set_solution_color(&mut self, color: String) {
+        self.solution_color = color;
+    }
+
+    pub fn set_solution_thickness(&mut self, thickness: f64) {
+        self.solution_thickness = thickness;
+    }
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Render the maze as a standalone SVG document
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let size = options.cell_size;
+        let margin = options.margin;
+        let width = self.width() as f64 * size + 2.0 * margin;
+        let height = self.height() as f64 * size + 2.0 * margin;
+        let mut svg = String::new();
+
+        // Writing into a String can't fail
+        writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = width,
+            h = height
+        ).unwrap();
+
+        // Fill the maze area with the default colour so only other cells
+        // need a rectangle of their own
+        let default_color = &options.type_colors[0];
+        if !default_color.is_empty() {
+            writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                margin, margin, width - 2.0 * margin, height - 2.0 * margin, escape_xml(default_color)
+            ).unwrap();
+        }
+
+        writeln!(svg, r#"  <g stroke="none">"#).unwrap();
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                let cell_type = self.get_cell(row, col).get_type();
+                let color = &options.type_colors[(cell_type as u8 >> 4) as usize];
+                if cell_type == CellType::Default || color.is_empty() {
+                    continue;
+                }
+                writeln!(
+                    svg,
+                    r#"    <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    margin + col as f64 * size, margin + row as f64 * size, size, size, escape_xml(color)
+                ).unwrap();
+            }
+        }
+        writeln!(svg, "  </g>").unwrap();
+
+        let mut path = String::new();
+        for (x1, y1, x2, y2) in wall_segments(self) {
+            let (x1, y1) = (margin + x1 as f64 * size, margin + y1 as f64 * size);
+            let (x2, y2) = (margin + x2 as f64 * size, margin + y2 as f64 * size);
+            if y1 == y2 {
+                write!(path, "M{} {}H{}", x1, y1, x2).unwrap();
+            } else {
+                write!(path, "M{} {}V{}", x1, y1, y2).unwrap();
+            }
+        }
+        // A stub at each end of a tunnel edge, the gap between them is
+        // where the passage goes under
+        let stub = TUNNEL_STUB * size;
+        for (x1, y1, x2, y2) in tunnel_edges(self) {
+            let (x1, y1) = (margin + x1 as f64 * size, margin + y1 as f64 * size);
+            let (x2, y2) = (margin + x2 as f64 * size, margin + y2 as f64 * size);
+            if y1 == y2 {
+                write!(path, "M{} {}H{}M{} {}H{}", x1, y1, x1 + stub, x2 - stub, y1, x2).unwrap();
+            } else {
+                write!(path, "M{} {}V{}M{} {}V{}", x1, y1, y1 + stub, x1, y2 - stub, y2).unwrap();
+            }
+        }
+        if !path.is_empty() {
+            writeln!(
+                svg,
+                r#"  <path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+                path, escape_xml(&options.wall_color), options.wall_thickness
+            ).unwrap();
+        }
+
+        if options.solution.len() > 1 {
+            let points: Vec<String> = options
+                .solution
+                .iter()
+                .map(|&(row, col)| {
+                    format!("{},{}", margin + (col as f64 + 0.5) * size, margin + (row as f64 + 0.5) * size)
+                })
+                .collect();
+            writeln!(
+                svg,
+                r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                points.join(" "), escape_xml(&options.solution_color), options.solution_thickness
+            ).unwrap();
+        }
+
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, WALL_N, WALL_S};
+
+    #[test]
+    fn merges_walls_into_segments() {
+        let mut maze = Maze::new(3, 1);
+        for col in 0..3 {
+            let mut cell = Cell::new(CellType::Default);
+            cell.add_wall(WALL_N | WALL_S);
+            maze.set_cell(0, col, cell);
+        }
+
+        let mut options = SvgOptions::new();
+        options.set_margin(0.0);
+        options.set_cell_size(10.0);
+        let svg = maze.to_svg(&options);
+
+        assert!(svg.contains(r#"d="M0 0H30M0 10H30""#), "{}", svg);
+    }
+}
@@ -0,0 +1,121 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::CellType,
+    maze::Maze,
+    render::{horizontal_edge, horizontal_tunnel, vertical_edge, vertical_tunnel}
+};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextStyle {
+    /// `┌──┬──┐` box-drawing characters
+    Unicode,
+    /// Classic `+--+--+` and `|`
+    Ascii,
+}
+
+// Box-drawing corner for each combination of joining walls, indexed by
+// up | right << 1 | down << 2 | left << 3
+const BOX_CORNERS: [char; 16] = [
+    ' ', '╵', '╶', '└', '╷', '│', '┌', '├',
+    '╴', '┘', '─', '┴', '┐', '┤', '┬', '┼',
+];
+
+#[wasm_bindgen]
+impl Maze {
+    /// Draw the maze's walls as text, two characters per cell. With
+    /// `markers` set, start, end and path cells are marked with `S`, `E`
+    /// and a dot. Edges where a weave passage goes under a crossing are
+    /// dotted.
+    pub fn to_text(&self, style: TextStyle, markers: bool) -> String {
+        let (width, height) = (self.width(), self.height());
+        let mut text = String::new();
+
+        for y in 0..=height {
+            // The line of corners and horizontal walls above row `y`
+            for x in 0..=width {
+                text.push(match style {
+                    TextStyle::Ascii => '+',
+                    TextStyle::Unicode => {
+                        let up = y > 0 && vertical_edge(self, x, y - 1);
+                        let right = x < width && horizontal_edge(self, y, x);
+                        let down = y < height && vertical_edge(self, x, y);
+                        let left = x > 0 && horizontal_edge(self, y, x - 1);
+                        BOX_CORNERS[up as usize | (right as usize) << 1 | (down as usize) << 2 | (left as usize) << 3]
+                    }
+                });
+                if x < width {
+                    let (wall, tunnel) = match style {
+                        TextStyle::Ascii => ("--", "::"),
+                        TextStyle::Unicode => ("──", "╌╌"),
+                    };
+                    text.push_str(if horizontal_edge(self, y, x) {
+                        wall
+                    } else if horizontal_tunnel(self, y, x) {
+                        tunnel
+                    } else {
+                        "  "
+                    });
+                }
+            }
+            text.push('\n');
+
+            if y == height {
+                break;
+            }
+
+            for x in 0..=width {
+                let (wall, tunnel) = match style {
+                    TextStyle::Ascii => ('|', ':'),
+                    TextStyle::Unicode => ('│', '╎'),
+                };
+                text.push(if vertical_edge(self, x, y) {
+                    wall
+                } else if vertical_tunnel(self, x, y) {
+                    tunnel
+                } else {
+                    ' '
+                });
+                if x < width {
+                    text.push_str(cell_text(self.get_cell(y, x).get_type(), style, markers));
+                }
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+fn cell_text(cell_type: CellType, style: TextStyle, markers: bool) -> &'static str {
+    match (cell_type, style) {
+        (CellType::Disabled, TextStyle::Ascii) => "##",
+        (CellType::Disabled, TextStyle::Unicode) => "░░",
+        (CellType::Start, _) if markers => "S ",
+        (CellType::End, _) if markers => "E ",
+        (CellType::Path, TextStyle::Ascii) if markers => ". ",
+        (CellType::Path, TextStyle::Unicode) if markers => "· ",
+        _ => "  ",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, WALL_E, WALL_N, WALL_S, WALL_W};
+
+    #[test]
+    fn draws_walls_and_markers() {
+        let mut maze = Maze::new(2, 1);
+        let mut start = Cell::new(CellType::Start);
+        start.add_wall(WALL_N | WALL_S | WALL_W);
+        maze.set_cell(0, 0, start);
+        let mut end = Cell::new(CellType::End);
+        end.add_wall(WALL_N | WALL_E | WALL_S);
+        maze.set_cell(0, 1, end);
+
+        assert_eq!(maze.to_text(TextStyle::Ascii, true), "+--+--+\n|S  E |\n+--+--+\n");
+        assert_eq!(maze.to_text(TextStyle::Unicode, false), "┌─────┐\n│     │\n└─────┘\n");
+    }
+}
@@ -1,7 +1,10 @@
 extern crate web_sys;
+use std::cell::Cell;
+
 use js_sys::Math;
 
 // A macro to provide `println!(..)`-style syntax for `console.log` logging.
+#[allow(unused_macros)]
 macro_rules! log {
     ( $( $t:tt )* ) => {
         web_sys::console::log_1(&format!( $( $t )* ).into());
@@ -20,23 +23,82 @@ pub fn set_panic_hook() {
 }
 
 pub fn choose_random_cell(rows: u32, cols: u32) -> (u32, u32) {
-    let row = (Math::random() * rows as f64).floor() as u32;
-    let col = (Math::random() * cols as f64).floor() as u32;
+    let row = (random() * rows as f64).floor() as u32;
+    let col = (random() * cols as f64).floor() as u32;
     (row, col)
 }
 
-pub fn pick_random_neighbor(row: u32, col: u32, width: u32, height: u32) -> (u32, u32) {
+/// Pick a random neighbour of a cell. With `wrap_x`/`wrap_y` set, cells on
+/// one edge also neighbour the cells on the opposite edge.
+pub fn pick_random_neighbor(
+    row: u32,
+    col: u32,
+    width: u32,
+    height: u32,
+    wrap_x: bool,
+    wrap_y: bool,
+) -> (u32, u32) {
     loop {
-        let dir = (Math::random() * 4.0).floor() as u32;
+        let dir = (random() * 4.0).floor() as u32;
 
         let (new_row, new_col) = match dir {
             0 if row > 0 => (row - 1, col),        // North
+            0 if wrap_y => (height - 1, col),
             1 if row + 1 < height => (row + 1, col), // South
+            1 if wrap_y => (0, col),
             2 if col > 0 => (row, col - 1),        // West
+            2 if wrap_x => (row, width - 1),
             3 if col + 1 < width => (row, col + 1), // East
+            3 if wrap_x => (row, 0),
             _ => continue,
         };
 
         return (new_row, new_col);
     }
 }
+
+/// Fisher-Yates shuffle driven by `Math.random`
+pub fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        // pick a random index j in [0..=i]
+        let j = (random() * ((i + 1) as f64)).floor() as usize;
+        items.swap(i, j);
+    }
+}
+
+thread_local! {
+    static RNG_STATE: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Seed the random source used by the generators, so the same seed always
+/// produces the same maze. `None` goes back to `Math.random`.
+pub fn seed_random(seed: Option<u32>) {
+    // Spread the seed over the whole state, xorshift can't start from zero
+    let state = seed.map(|seed| (seed as u64 ^ 0x9E37_79B9_7F4A_7C15).wrapping_mul(0xBF58_476D_1CE4_E5B9) | 1);
+    RNG_STATE.with(|rng| rng.set(state));
+}
+
+/// A random number in `[0, 1)`, from the seeded generator if there is one
+pub fn random() -> f64 {
+    RNG_STATE.with(|rng| match rng.get() {
+        Some(mut state) => {
+            // xorshift64*
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            rng.set(Some(state));
+            (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+        }
+        None => Math::random(),
+    })
+}
+
+/// Escape text for an XML attribute or element. SVG colours and Tiled
+/// image paths come from the caller and must not break out of the quotes.
+pub fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
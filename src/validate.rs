@@ -0,0 +1,196 @@
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    cell::{CellType, WALL_E, WALL_N, WALL_S, WALL_W},
+    maze::Maze
+};
+
+/// Everything `Maze::validate` found wrong. Positions are flattened as
+/// `[row, col, wall, row, col, wall, ...]` or `[row, col, ...]`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    asymmetric_walls: Vec<u32>,
+    missing_boundary_walls: Vec<u32>,
+    unreachable_cells: Vec<u32>,
+    cycles: u32,
+}
+
+#[wasm_bindgen]
+impl ValidationReport {
+    /// Walls set on one side of a shared edge only, as the cell and the
+    /// `WALL_*` bit that has no match on the neighbour
+    pub fn asymmetric_walls(&self) -> Vec<u32> {
+        self.asymmetric_walls.clone()
+    }
+
+    /// Open edges on the outside of the maze, or towards a disabled cell,
+    /// other than the entrance and exit
+    pub fn missing_boundary_walls(&self) -> Vec<u32> {
+        self.missing_boundary_walls.clone()
+    }
+
+    /// Enabled cells that can't be reached from the start, or from the
+    /// first enabled cell if there is no start
+    pub fn unreachable_cells(&self) -> Vec<u32> {
+        self.unreachable_cells.clone()
+    }
+
+    pub fn cycles(&self) -> u32 {
+        self.cycles
+    }
+
+    /// Walls agree on both sides and the outside is closed
+    pub fn is_consistent(&self) -> bool {
+        self.asymmetric_walls.is_empty() && self.missing_boundary_walls.is_empty()
+    }
+
+    /// Consistent, with exactly one route between any two cells
+    pub fn is_perfect(&self) -> bool {
+        self.is_consistent() && self.unreachable_cells.is_empty() && self.cycles == 0
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_perfect() {
+            return write!(f, "perfect maze");
+        }
+
+        let mut problems = Vec::new();
+        for wall in self.asymmetric_walls.chunks(3) {
+            problems.push(format!("half wall {:#06b} at ({}, {})", wall[2], wall[0], wall[1]));
+        }
+        for wall in self.missing_boundary_walls.chunks(3) {
+            problems.push(format!("open boundary {:#06b} at ({}, {})", wall[2], wall[0], wall[1]));
+        }
+        if !self.unreachable_cells.is_empty() {
+            problems.push(format!("{} unreachable cells", self.unreachable_cells.len() / 2));
+        }
+        if self.cycles > 0 {
+            problems.push(format!("{} cycles", self.cycles));
+        }
+        write!(f, "{}", problems.join(", "))
+    }
+}
+
+#[wasm_bindgen]
+impl Maze {
+    /// Check the walls for consistency and whether the maze is perfect
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if !self.is_enabled(row, col) {
+                    continue;
+                }
+                let cell = self.get_cell(row, col);
+
+                for &(wall, back, dr, dc) in &[
+                    (WALL_N, WALL_S, -1, 0),
+                    (WALL_E, WALL_W, 0, 1),
+                    (WALL_S, WALL_N, 1, 0),
+                    (WALL_W, WALL_E, 0, -1),
+                ] {
+                    let here = cell.has_wall(wall);
+                    let (nr, nc) = match self.enabled_neighbor(row, col, dr, dc) {
+                        Some(neighbor) => neighbor,
+                        None => {
+                            if !here && !self.is_opening(row, col, wall) {
+                                report.missing_boundary_walls.extend_from_slice(&[row, col, wall as u32]);
+                            }
+                            continue;
+                        }
+                    };
+
+                    // A weave tunnel keeps the wall of the cell it passes under
+                    let there = self.get_cell(nr, nc).has_wall(back);
+                    let tunnel = here && self.is_under_crossing(row, col) || there && self.is_under_crossing(nr, nc);
+                    if here && !there && !tunnel {
+                        report.asymmetric_walls.extend_from_slice(&[row, col, wall as u32]);
+                    }
+                }
+            }
+        }
+
+        let start = (0..self.height())
+            .flat_map(|row| (0..self.width()).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.is_enabled(row, col))
+            .min_by_key(|&(row, col)| self.get_cell(row, col).get_type() != CellType::Start);
+        if let Some((row, col)) = start {
+            let distances = self.distances_from(row, col);
+            for (i, distance) in distances.iter().enumerate() {
+                let (row, col) = (i as u32 / self.width(), i as u32 % self.width());
+                if distance.is_none() && self.is_enabled(row, col) {
+                    report.unreachable_cells.extend_from_slice(&[row, col]);
+                }
+            }
+        }
+
+        report.cycles = self.loop_count();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cell::Cell, generators::{MazeBuilder, GENERATOR_NAMES}};
+
+    #[test]
+    fn generators_make_perfect_mazes() {
+        for name in GENERATOR_NAMES {
+            for seed in 1..=5 {
+                let (_, maze) = MazeBuilder::generated(name, seed, &Maze::new(9, 7));
+                let report = maze.validate();
+                assert!(report.is_perfect(), "{} with seed {}: {}", name, seed, report);
+            }
+        }
+    }
+
+    #[test]
+    fn generators_respect_wrapping_and_masks() {
+        let mut wrapped = Maze::new(8, 6);
+        wrapped.set_wrap(true, true);
+        let mut masked = Maze::new(8, 6);
+        masked.set_mask_ascii("########\n#### ###\n########\n##  ####\n########\n########");
+        // Two regions the generators have to fill separately
+        let mut split = Maze::new(9, 4);
+        split.set_mask_ascii("####.####\n#..#.#.##\n##.#.#.##\n####.####");
+
+        for name in GENERATOR_NAMES {
+            for seed in 1..=3 {
+                let report = MazeBuilder::generated(name, seed, &wrapped).1.validate();
+                assert!(report.is_perfect(), "{} wrapped, seed {}: {}", name, seed, report);
+
+                let report = MazeBuilder::generated(name, seed, &masked).1.validate();
+                assert!(report.is_perfect(), "{} masked, seed {}: {}", name, seed, report);
+
+                let (_, maze) = MazeBuilder::generated(name, seed, &split);
+                let report = maze.validate();
+                assert!(report.is_consistent(), "{} split, seed {}: {}", name, seed, report);
+                assert_eq!(report.cycles(), 0, "{} split, seed {}", name, seed);
+                assert_eq!(maze.metrics().components(), 2, "{} split, seed {}", name, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_half_walls() {
+        let mut maze = Maze::new(2, 1);
+        let mut left = Cell::new(CellType::Default);
+        left.add_wall(WALL_N | WALL_E | WALL_S | WALL_W);
+        maze.set_cell(0, 0, left);
+        let mut right = Cell::new(CellType::Default);
+        right.add_wall(WALL_N | WALL_E | WALL_S);
+        maze.set_cell(0, 1, right);
+
+        let report = maze.validate();
+        assert_eq!(report.asymmetric_walls(), vec![0, 0, WALL_E as u32]);
+        assert!(report.missing_boundary_walls().is_empty());
+        assert!(!report.is_perfect());
+    }
+}
@@ -0,0 +1,20 @@
+//! The Rust API outside of the browser.
+
+use mazeweb::{FormatError, Maze, Opening, WALL_N};
+
+#[test]
+fn json_round_trip() {
+    let mut maze = Maze::new(4, 3);
+    maze.set_opening(Opening::Entrance, 0, 1, WALL_N);
+
+    let loaded = Maze::from_json(&maze.to_json()).unwrap();
+    assert_eq!(loaded.get_cells(), maze.get_cells());
+    assert_eq!(loaded.get_opening(Opening::Entrance), Some((0, 1, WALL_N)));
+    assert!(matches!(Maze::from_json("{}"), Err(FormatError::Syntax(_))));
+}
+
+#[test]
+fn png_export() {
+    let png = Maze::new(4, 3).to_png(8);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+}
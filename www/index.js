@@ -1,4 +1,4 @@
-import init, { Maze, Cell, MazeBuilder, CellType } from "../pkg/mazeweb.js";
+import init, { Maze, Cell, MazeBuilder, CellType, Placement, SharedMaze } from "../pkg/mazeweb.js";
 
 
 // === Constants ===
@@ -44,6 +44,8 @@ const stepBackButton = document.getElementById("step-back");
 const speedInput = document.getElementById("speed");
 const stepSlider = document.getElementById("step-slider");
 const stepLabel = document.getElementById("step-label");
+const placementSelect = document.getElementById("placement");
+const placeEndsButton = document.getElementById("place-ends");
 
 // === Utility Functions ===
 const getIndex = (row, column) => row * width + column;
@@ -60,6 +62,7 @@ const drawMaze = () => {
 
   drawOutline();
   drawCells();
+  if (animationIntervalId === null) shareMaze();
 }
 
 const drawOutline = () => {
@@ -76,31 +79,40 @@ const drawOutline = () => {
   ctx.stroke();
 };
 
-const drawWalls = (cellValue, row, col, size) => {
+// Share of a tunnel edge covered by the stub at each end, the same as
+// `TUNNEL_STUB` in src/render/mod.rs
+const TUNNEL_STUB = 0.25;
+
+// A weave crossing keeps its walls on the sides a passage tunnels through.
+// Those are drawn as a stub at each end so the gap shows the tunnel.
+const drawWalls = (cellValue, row, col, size, under) => {
   const x = col * (size + 1) + 1;
   const y = row * (size + 1) + 1;
+  const stub = under ? size * TUNNEL_STUB : size / 2;
 
   ctx.strokeStyle = WALL_COLOR;
   ctx.lineWidth = 4;
 
   ctx.beginPath();
 
-  if (cellValue & WALL_N) {
-    ctx.moveTo(x, y);
-    ctx.lineTo(x + size, y);
-  }
-  if (cellValue & WALL_E) {
-    ctx.moveTo(x + size, y);
-    ctx.lineTo(x + size, y + size);
-  }
-  if (cellValue & WALL_S) {
-    ctx.moveTo(x, y + size);
-    ctx.lineTo(x + size, y + size);
-  }
-  if (cellValue & WALL_W) {
-    ctx.moveTo(x, y);
-    ctx.lineTo(x, y + size);
-  }
+  const edge = (x1, y1, x2, y2) => {
+    if (x1 === x2) {
+      ctx.moveTo(x1, y1);
+      ctx.lineTo(x1, y1 + stub);
+      ctx.moveTo(x1, y2 - stub);
+      ctx.lineTo(x1, y2);
+    } else {
+      ctx.moveTo(x1, y1);
+      ctx.lineTo(x1 + stub, y1);
+      ctx.moveTo(x2 - stub, y1);
+      ctx.lineTo(x2, y1);
+    }
+  };
+
+  if (cellValue & WALL_N) edge(x, y, x + size, y);
+  if (cellValue & WALL_E) edge(x + size, y, x + size, y + size);
+  if (cellValue & WALL_S) edge(x, y + size, x + size, y + size);
+  if (cellValue & WALL_W) edge(x, y, x, y + size);
 
   ctx.stroke();
 };
@@ -147,7 +159,7 @@ const drawCells = () => {
         cell_size
       );
 
-      drawWalls(cellValue, row, col, cell_size); // << render walls
+      drawWalls(cellValue, row, col, cell_size, maze.is_under_crossing(row, col)); // << render walls
     }
   }
 
@@ -176,6 +188,47 @@ const updateMaze = () => {
   drawMaze();
 };
 
+// Keep the URL hash on a share code of the maze as it is now, so the
+// address can be copied to reproduce it
+const shareMaze = () => {
+  const code = builder
+    ? maze.to_share_code_with(builder.generator_name(), builder.seed())
+    : maze.to_share_code();
+  history.replaceState(null, "", `#${code}`);
+};
+
+const loadSharedMaze = () => {
+  const code = location.hash.slice(1);
+  if (!code) return false;
+
+  let shared;
+  try {
+    shared = SharedMaze.decode(code);
+  } catch (err) {
+    console.error("Ignoring share code:", err);
+    return false;
+  }
+
+  stopAnimation();
+  builder = null;
+  maze = shared.maze();
+  width = maze.width();
+  height = maze.height();
+  widthInput.value = width;
+  heightInput.value = height;
+  const generator = shared.generator();
+  if (generator && [...generatorSelect.options].some(option => option.value === generator)) {
+    generatorSelect.value = generator;
+  }
+
+  stepSlider.value = 0;
+  stepSlider.max = 0;
+  updateStepLabel();
+  resizeCanvas();
+  drawMaze();
+  return true;
+};
+
 function updateStepLabel() {
   if (!builder) {
     stepLabel.textContent = "Step: 0 / 0";
@@ -251,6 +304,13 @@ stepForwardButton.addEventListener("click", () => {
   }
 });
 
+placeEndsButton.addEventListener("click", () => {
+  stopAnimation();
+  const ends = maze.place_start_end(Placement[placementSelect.value]);
+  if (ends.length === 0) console.warn("No two connected cells to place the start and end on");
+  drawMaze();
+});
+
 stepBackButton.addEventListener("click", () => {
   if (builder && builder.step_backward(maze)) {
     stepSlider.value = builder.current_step();
@@ -290,6 +350,7 @@ function stopAnimation() {
   clearInterval(animationIntervalId);
   animationIntervalId = null;
   updatePlayPauseButton();
+  shareMaze();
 }
 
 function goToStep(stepIndex) {
@@ -307,6 +368,8 @@ function goToStep(stepIndex) {
 // === Startup ===
 stepSlider.value = 0;
 stepSlider.max = 0;
-updateMaze();
+// A share code in the address wins over the default size
+if (!loadSharedMaze()) updateMaze();
 resizeCanvas();
 drawMaze();
+window.addEventListener("hashchange", loadSharedMaze);